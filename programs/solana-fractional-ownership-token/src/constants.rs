@@ -1,8 +1,26 @@
-pub const MIN_LOCK_DURATION: i64 = 7 * 24 * 60 * 60;
-pub const MAX_LOCK_DURATION: i64 = 4 * 365 * 24 * 60 * 60;
-pub const MAX_LOCK_MULTIPLIER: u64 = 4;
+pub const VE_SLOPE_PRECISION: u128 = 1_000_000_000_000;
+
+pub const WEEK: i64 = 7 * 24 * 60 * 60;
+pub const SLOPE_SCHEDULE_WEEKS: usize = 264;
+pub const MAX_SUPPORTED_LOCK_DURATION: i64 = (SLOPE_SCHEDULE_WEEKS as i64 - 2) * WEEK;
+pub const LOCK_MULTIPLIER_UPDATE_DELAY: i64 = 2 * 24 * 60 * 60;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const DEFAULT_EARLY_WITHDRAW_PENALTY_BPS: u16 = 10_000;
+pub const DEFAULT_EARLY_WITHDRAW_PENALTY_CAP_BPS: u16 = 5_000;
+pub const BATCH_CLAIM_COMPUTE_RESERVE: u64 = 40_000; // Stop starting new batch entries below this
 
 pub const GLOBAL_STATE_SEED: &[u8] = b"global-state";
 pub const USER_LOCK_SEED: &[u8] = b"user-lock";
 pub const FEE_VAULT_SEED: &[u8] = b"fee-vault";
 pub const TOKEN_VAULT_SEED: &[u8] = b"token-vault";
+pub const SLOPE_SCHEDULE_SEED: &[u8] = b"slope-schedule";
+pub const FEE_EPOCH_SEED: &[u8] = b"fee-epoch";
+pub const REWARD_TOKEN_SEED: &[u8] = b"reward-token";
+pub const USER_REWARD_SEED: &[u8] = b"user-reward";
+pub const LOCK_POINT_SEED: &[u8] = b"lock-point";
+pub const POSITION_MINT_SEED: &[u8] = b"position-mint";
+pub const VE_ESCROW_SEED: &[u8] = b"ve-escrow";
+pub const FAUCET_STATE_SEED: &[u8] = b"faucet-state";
+pub const FAUCET_CLAIM_SEED: &[u8] = b"faucet-claim";
+
+pub const MAX_FAUCET_ALLOWLIST: usize = 8;
@@ -5,9 +5,15 @@ pub enum ErrorCode {
     #[msg("Invalid amount: must be greater than 0")]
     InvalidAmount,
 
-    #[msg("Invalid lock duration: must be between 7 days and 4 years")]
+    #[msg("Invalid lock duration: must be between the configured min and max")]
     InvalidLockDuration,
 
+    #[msg("Invalid lock duration bounds: min must be positive and below max")]
+    InvalidLockDurationBounds,
+
+    #[msg("Invalid lock multiplier: numerator must be >= denominator > 0 and within the max multiplier")]
+    InvalidLockMultiplier,
+
     #[msg("Existing lock has not expired yet")]
     ExistingLockNotExpired,
 
@@ -26,6 +32,69 @@ pub enum ErrorCode {
     #[msg("No fees available to claim")]
     NoFeesToClaim,
 
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthority,
+
+    #[msg("Faucet allowlist is too long")]
+    FaucetAllowlistTooLong,
+
+    #[msg("Faucet mint exceeds the per-request or total cap")]
+    FaucetCapExceeded,
+
+    #[msg("Faucet cooldown has not elapsed for this wallet")]
+    FaucetCooldown,
+
+    #[msg("No lock multiplier update is queued")]
+    NoPendingMultiplierUpdate,
+
+    #[msg("Queued lock multiplier update is not yet effective")]
+    MultiplierUpdateNotReady,
+
+    #[msg("Fee epoch account does not match the expected epoch")]
+    InvalidFeeEpoch,
+
+    #[msg("Lock point does not belong to this lock or is out of order")]
+    InvalidLockPoint,
+
+    #[msg("Reward duration must be positive and yield a non-zero reward rate")]
+    InvalidRewardDuration,
+
+    #[msg("Treasury and burn shares cannot exceed 100%")]
+    InvalidFeeSplit,
+
+    #[msg("Fee recipient must be set")]
+    InvalidFeeRecipient,
+
+    #[msg("Batch claim entry does not match its lock")]
+    InvalidClaimEntry,
+
+    #[msg("Position already holds a lock; add to it or open another position")]
+    PositionInUse,
+
+    #[msg("Position is not held by this wallet or is listed out of order")]
+    InvalidPosition,
+
+    #[msg("Signer does not hold this position's NFT")]
+    NotPositionHolder,
+
+    #[msg("Claim delegation was set by a previous holder of the position")]
+    StaleClaimDelegation,
+
+    #[msg("Early withdraw penalty and cap cannot exceed 100%")]
+    InvalidPenaltyConfig,
+
+    #[msg("Treasury token account is required while a treasury share is set")]
+    MissingTreasuryAccount,
+
+    #[msg("Only the base mint's mint authority can initialize an instance for it")]
+    NotMintAuthority,
+
+    #[msg("The source position has closed fee epochs left to settle")]
+    UnsettledFees,
+
     #[msg("Math overflow")]
     MathOverflow,
 }
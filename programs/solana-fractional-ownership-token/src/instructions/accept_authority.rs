@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        constraint = new_authority.key() == global_state.pending_authority
+    )]
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn handler(ctx: Context<AcceptAuthority>) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+
+    require!(
+        global_state.pending_authority != Pubkey::default(),
+        ErrorCode::NoPendingAuthority
+    );
+
+    global_state.authority = ctx.accounts.new_authority.key();
+    global_state.pending_authority = Pubkey::default();
+
+    msg!("Authority transferred to {}", global_state.authority);
+
+    Ok(())
+}
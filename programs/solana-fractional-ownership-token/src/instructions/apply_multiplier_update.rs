@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct ApplyMultiplierUpdate<'info> {
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn handler(ctx: Context<ApplyMultiplierUpdate>) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        global_state.lock_multiplier_effective_at != 0,
+        ErrorCode::NoPendingMultiplierUpdate
+    );
+    require!(
+        current_time >= global_state.lock_multiplier_effective_at,
+        ErrorCode::MultiplierUpdateNotReady
+    );
+
+    global_state.lock_multiplier_numerator = global_state.pending_lock_multiplier_numerator;
+    global_state.lock_multiplier_denominator = global_state.pending_lock_multiplier_denominator;
+    global_state.pending_lock_multiplier_numerator = 0;
+    global_state.pending_lock_multiplier_denominator = 0;
+    global_state.lock_multiplier_effective_at = 0;
+
+    msg!(
+        "Lock multiplier updated to {}/{}",
+        global_state.lock_multiplier_numerator,
+        global_state.lock_multiplier_denominator
+    );
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+use crate::utils::{checkpoint_ve_supply, current_ve_supply};
+
+#[derive(Accounts)]
+pub struct Checkpoint<'info> {
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+}
+
+pub fn handler(ctx: Context<Checkpoint>) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let global_state = &mut ctx.accounts.global_state;
+
+    checkpoint_ve_supply(global_state, &mut ctx.accounts.slope_schedule, current_time)?;
+
+    msg!("veSupply at {}: {}", current_time, current_ve_supply(global_state));
+
+    Ok(())
+}
@@ -1,13 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token_2022::{self, Token2022, TransferChecked},
-    token_interface::{Mint, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
 use crate::constants::*;
 use crate::error::ErrorCode;
 use crate::state::*;
-use crate::utils::calculate_current_ve_balance;
+use crate::utils::{
+    accrue_epoch_rewards, pay_from_fee_vault, sync_fee_epoch, week_start, write_user_reward,
+};
 
 #[derive(Accounts)]
 pub struct ClaimFees<'info> {
@@ -15,28 +16,61 @@ pub struct ClaimFees<'info> {
     pub user: Signer<'info>,
 
     #[account(
-        mut,
-        seeds = [USER_LOCK_SEED, user.key().as_ref()],
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            user_lock.user.as_ref(),
+            &user_lock.position_id.to_le_bytes()
+        ],
         bump = user_lock.bump
     )]
     pub user_lock: Account<'info, UserLock>,
 
+    #[account(
+        token::mint = user_lock.position_mint,
+        token::authority = user,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<UserReward>(),
+        seeds = [USER_REWARD_SEED, user_lock.key().as_ref(), reward_mint.key().as_ref()],
+        bump
+    )]
+    pub user_reward: Account<'info, UserReward>,
+
     #[account(
         mut,
-        seeds = [GLOBAL_STATE_SEED],
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
         bump = global_state.bump
     )]
     pub global_state: Account<'info, GlobalState>,
 
     #[account(
         mut,
-        constraint = base_mint.key() == global_state.base_mint
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
     )]
-    pub base_mint: InterfaceAccount<'info, Mint>,
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
 
     #[account(
         mut,
-        associated_token::mint = base_mint,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), reward_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Account<'info, RewardToken>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
         associated_token::authority = user,
         associated_token::token_program = token_program
     )]
@@ -44,71 +78,74 @@ pub struct ClaimFees<'info> {
 
     #[account(
         mut,
-        seeds = [FEE_VAULT_SEED],
-        bump,
-        constraint = fee_vault.key() == global_state.fee_vault
+        constraint = fee_vault.key() == reward_token.vault
     )]
     pub fee_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token2022>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            reward_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Account<'info, FeeEpoch>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<ClaimFees>) -> Result<()> {
-    let user_lock = &mut ctx.accounts.user_lock;
     let current_time = Clock::get()?.unix_timestamp;
-
-    require!(user_lock.locked_amount > 0, ErrorCode::NoExistingLock);
-
-    let current_ve_balance = calculate_current_ve_balance(
-        user_lock.initial_ve_amount,
-        user_lock.lock_start_time,
-        user_lock.unlock_time,
+    let user_lock_key = ctx.accounts.user_lock.key();
+    let global_state_key = ctx.accounts.global_state.key();
+
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
         current_time,
     )?;
 
-    require!(current_ve_balance > 0, ErrorCode::NoVotingPower);
-
-    let cumulative_fee_per_ve_token = ctx.accounts.global_state.cumulative_fee_per_ve_token;
-    let pending_fees = (current_ve_balance as u128)
-        .checked_mul(
-            cumulative_fee_per_ve_token
-                .checked_sub(user_lock.fee_debt)
-                .ok_or(ErrorCode::MathOverflow)?
-        )
-        .ok_or(ErrorCode::MathOverflow)?
-        .checked_div(1_000_000_000_000_000_000)
-        .ok_or(ErrorCode::MathOverflow)? as u64;
-
+    let user_reward = &mut ctx.accounts.user_reward;
+    write_user_reward(
+        user_reward,
+        user_lock_key,
+        ctx.accounts.reward_mint.key(),
+        ctx.accounts.reward_token.start_epoch,
+        ctx.bumps.user_reward,
+    );
+
+    // Lock points and closed epochs are passed in remaining_accounts
+    let pending_fees = accrue_epoch_rewards(
+        &ctx.accounts.user_lock,
+        &user_lock_key,
+        user_reward,
+        &ctx.accounts.global_state.base_mint,
+        ctx.remaining_accounts,
+        current_time,
+        ctx.program_id,
+    )?;
     require!(pending_fees > 0, ErrorCode::NoFeesToClaim);
 
-    let global_state_bump = ctx.accounts.global_state.bump;
-    let seeds = &[GLOBAL_STATE_SEED, &[global_state_bump]];
-    let signer_seeds = &[&seeds[..]];
-
-    let transfer_accounts = TransferChecked {
-        from: ctx.accounts.fee_vault.to_account_info(),
-        mint: ctx.accounts.base_mint.to_account_info(),
-        to: ctx.accounts.user_token_account.to_account_info(),
-        authority: ctx.accounts.global_state.to_account_info(),
-    };
-
-    token_2022::transfer_checked(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            transfer_accounts,
-            signer_seeds,
-        ),
+    pay_from_fee_vault(
+        &ctx.accounts.global_state,
+        &ctx.accounts.fee_vault,
+        &ctx.accounts.reward_mint,
+        ctx.accounts.user_token_account.to_account_info(),
+        &ctx.accounts.token_program,
         pending_fees,
-        ctx.accounts.base_mint.decimals,
     )?;
 
-    user_lock.fee_debt = cumulative_fee_per_ve_token;
-    user_lock.fees_claimed = user_lock.fees_claimed
-        .checked_add(pending_fees)
-        .ok_or(ErrorCode::MathOverflow)?;
-
-    msg!("Claimed {} fees", pending_fees);
-    msg!("veBalance: {}", current_ve_balance);
+    msg!("Claimed {} {} fees", pending_fees, ctx.accounts.reward_mint.key());
 
     Ok(())
 }
@@ -0,0 +1,245 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::compute_units::sol_remaining_compute_units;
+use anchor_lang::system_program::{self, CreateAccount};
+use anchor_spl::{
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::{
+    accrue_shared_epoch_rewards, pay_from_fee_vault, sync_fee_epoch, week_start,
+};
+
+#[derive(Accounts)]
+pub struct ClaimFeesBatch<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), reward_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Account<'info, RewardToken>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.key() == reward_token.vault
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            reward_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Account<'info, FeeEpoch>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// `remaining_accounts` holds `epoch_count` fee epochs for consecutive weeks
+/// from `first_epoch`, shared by every entry, followed by one group per entry:
+/// `[user_lock, user_reward, position_token_account, recipient_token_account,
+/// lock points...]` with `point_counts[i]` lock points.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClaimFeesBatch<'info>>,
+    first_epoch: i64,
+    epoch_count: u8,
+    point_counts: Vec<u8>,
+) -> Result<()> {
+    require!(first_epoch % WEEK == 0, ErrorCode::InvalidFeeEpoch);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let global_state_key = ctx.accounts.global_state.key();
+    let reward_mint_key = ctx.accounts.reward_mint.key();
+
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+
+    let remaining = ctx.remaining_accounts;
+    let epoch_count = epoch_count as usize;
+    require!(remaining.len() >= epoch_count, ErrorCode::InvalidClaimEntry);
+    let (epoch_accounts, mut entries) = remaining.split_at(epoch_count);
+
+    let mut processed: u32 = 0;
+    let mut paid: u32 = 0;
+    let mut total_paid: u64 = 0;
+
+    for point_count in point_counts {
+        if sol_remaining_compute_units() < BATCH_CLAIM_COMPUTE_RESERVE {
+            break;
+        }
+
+        let group_len = 4 + point_count as usize;
+        require!(entries.len() >= group_len, ErrorCode::InvalidClaimEntry);
+        let (group, rest) = entries.split_at(group_len);
+        entries = rest;
+
+        let user_lock_info = &group[0];
+        let user_reward_info = &group[1];
+        let position_info = &group[2];
+        let recipient_info = &group[3];
+        let lock_points = &group[4..];
+
+        let user_lock = Account::<UserLock>::try_from(user_lock_info)?;
+        let expected_user_lock = Pubkey::create_program_address(
+            &[
+                USER_LOCK_SEED,
+                base_mint_key.as_ref(),
+                user_lock.user.as_ref(),
+                &user_lock.position_id.to_le_bytes(),
+                &[user_lock.bump],
+            ],
+            ctx.program_id,
+        )
+        .map_err(|_| ErrorCode::InvalidClaimEntry)?;
+        require_keys_eq!(user_lock_info.key(), expected_user_lock, ErrorCode::InvalidClaimEntry);
+
+        let position = InterfaceAccount::<TokenAccount>::try_from(position_info)?;
+        require_keys_eq!(position.mint, user_lock.position_mint, ErrorCode::InvalidClaimEntry);
+        require!(position.amount == 1, ErrorCode::InvalidClaimEntry);
+
+        let recipient = InterfaceAccount::<TokenAccount>::try_from(recipient_info)?;
+        require_keys_eq!(recipient.mint, reward_mint_key, ErrorCode::InvalidClaimEntry);
+        require_keys_eq!(recipient.owner, user_lock.fee_recipient, ErrorCode::InvalidClaimEntry);
+
+        let (expected_user_reward, user_reward_bump) = Pubkey::find_program_address(
+            &[USER_REWARD_SEED, user_lock_info.key.as_ref(), reward_mint_key.as_ref()],
+            ctx.program_id,
+        );
+        require_keys_eq!(user_reward_info.key(), expected_user_reward, ErrorCode::InvalidClaimEntry);
+
+        let mut user_reward = if user_reward_info.data_is_empty() {
+            UserReward {
+                user_lock: user_lock_info.key(),
+                reward_mint: reward_mint_key,
+                epoch_cursor: ctx.accounts.reward_token.start_epoch,
+                point_index: 0,
+                fees_claimed: 0,
+                bump: user_reward_bump,
+            }
+        } else {
+            require_keys_eq!(*user_reward_info.owner, *ctx.program_id, ErrorCode::InvalidClaimEntry);
+            UserReward::try_deserialize(&mut &user_reward_info.try_borrow_data()?[..])?
+        };
+
+        processed += 1;
+
+        // The recipient was chosen by an earlier holder of the position NFT
+        if position.owner != user_lock.delegated_by {
+            continue;
+        }
+
+        // Epochs are looked up by week, so a cursor that jumps over weeks the
+        // lock had no line in stays aligned with the shared run. A bad entry
+        // is skipped rather than failing the crank; a failed accrual leaves
+        // every account as it was
+        let Ok(pending_fees) = accrue_shared_epoch_rewards(
+            &user_lock,
+            user_lock_info.key,
+            &mut user_reward,
+            &base_mint_key,
+            lock_points,
+            epoch_accounts,
+            first_epoch,
+            current_time,
+            ctx.program_id,
+        ) else {
+            continue;
+        };
+
+        if user_reward_info.data_is_empty() {
+            // Nothing to record for a lock that has never earned this mint
+            if pending_fees == 0 {
+                continue;
+            }
+
+            let space = 8 + std::mem::size_of::<UserReward>();
+            let user_reward_seeds = &[
+                USER_REWARD_SEED,
+                user_lock_info.key.as_ref(),
+                reward_mint_key.as_ref(),
+                &[user_reward_bump],
+            ];
+
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    CreateAccount {
+                        from: ctx.accounts.caller.to_account_info(),
+                        to: user_reward_info.clone(),
+                    },
+                    &[&user_reward_seeds[..]],
+                ),
+                Rent::get()?.minimum_balance(space),
+                space as u64,
+                ctx.program_id,
+            )?;
+        }
+        user_reward.try_serialize(&mut &mut user_reward_info.try_borrow_mut_data()?[..])?;
+        if pending_fees == 0 {
+            continue;
+        }
+
+        pay_from_fee_vault(
+            &ctx.accounts.global_state,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.reward_mint,
+            recipient_info.clone(),
+            &ctx.accounts.token_program,
+            pending_fees,
+        )?;
+
+        paid += 1;
+        total_paid = total_paid.checked_add(pending_fees).ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    msg!(
+        "Batch claimed {} {} fees for {} of {} locks processed",
+        total_paid,
+        reward_mint_key,
+        paid,
+        processed
+    );
+
+    Ok(())
+}
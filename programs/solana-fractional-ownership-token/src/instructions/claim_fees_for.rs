@@ -0,0 +1,160 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::{
+    accrue_epoch_rewards, pay_from_fee_vault, sync_fee_epoch, week_start, write_user_reward,
+};
+
+#[derive(Accounts)]
+pub struct ClaimFeesFor<'info> {
+    #[account(
+        mut,
+        constraint = caller.key() == position_token_account.owner || caller.key() == user_lock.delegate
+    )]
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            user_lock.user.as_ref(),
+            &user_lock.position_id.to_le_bytes()
+        ],
+        bump = user_lock.bump
+    )]
+    pub user_lock: Account<'info, UserLock>,
+
+    #[account(
+        token::mint = user_lock.position_mint,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder,
+        constraint = position_token_account.owner == user_lock.delegated_by @ ErrorCode::StaleClaimDelegation
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = 8 + std::mem::size_of::<UserReward>(),
+        seeds = [USER_REWARD_SEED, user_lock.key().as_ref(), reward_mint.key().as_ref()],
+        bump
+    )]
+    pub user_reward: Account<'info, UserReward>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), reward_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Account<'info, RewardToken>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::token_program = token_program,
+        constraint = recipient_token_account.owner == user_lock.fee_recipient
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.key() == reward_token.vault
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            reward_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Account<'info, FeeEpoch>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<ClaimFeesFor>) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let user_lock_key = ctx.accounts.user_lock.key();
+    let global_state_key = ctx.accounts.global_state.key();
+
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
+    let user_reward = &mut ctx.accounts.user_reward;
+    write_user_reward(
+        user_reward,
+        user_lock_key,
+        ctx.accounts.reward_mint.key(),
+        ctx.accounts.reward_token.start_epoch,
+        ctx.bumps.user_reward,
+    );
+
+    // Lock points and closed epochs are passed in remaining_accounts
+    let pending_fees = accrue_epoch_rewards(
+        &ctx.accounts.user_lock,
+        &user_lock_key,
+        user_reward,
+        &ctx.accounts.global_state.base_mint,
+        ctx.remaining_accounts,
+        current_time,
+        ctx.program_id,
+    )?;
+    require!(pending_fees > 0, ErrorCode::NoFeesToClaim);
+
+    pay_from_fee_vault(
+        &ctx.accounts.global_state,
+        &ctx.accounts.fee_vault,
+        &ctx.accounts.reward_mint,
+        ctx.accounts.recipient_token_account.to_account_info(),
+        &ctx.accounts.token_program,
+        pending_fees,
+    )?;
+
+    msg!(
+        "Claimed {} {} fees for {} to {}",
+        pending_fees,
+        ctx.accounts.reward_mint.key(),
+        ctx.accounts.user_lock.user,
+        ctx.accounts.recipient_token_account.key()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,279 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{self, Mint, TokenAccount, TokenInterface, MintTo},
+};
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::{
+    accrue_epoch_rewards, calculate_time_multiplier, calculate_ve_point, pay_from_fee_vault,
+    record_lock_point, sync_fee_epoch, update_ve_supply, week_start, write_user_reward,
+};
+
+#[derive(Accounts)]
+pub struct CompoundFees<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            user_lock.user.as_ref(),
+            &user_lock.position_id.to_le_bytes()
+        ],
+        bump = user_lock.bump
+    )]
+    pub user_lock: Account<'info, UserLock>,
+
+    #[account(
+        token::mint = user_lock.position_mint,
+        token::authority = user,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LockPoint>(),
+        seeds = [LOCK_POINT_SEED, user_lock.key().as_ref(), &user_lock.point_count.to_le_bytes()],
+        bump
+    )]
+    pub lock_point: Account<'info, LockPoint>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<UserReward>(),
+        seeds = [USER_REWARD_SEED, user_lock.key().as_ref(), base_mint.key().as_ref()],
+        bump
+    )]
+    pub user_reward: Account<'info, UserReward>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), base_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Box<Account<'info, RewardToken>>,
+
+    #[account(
+        constraint = base_mint.key() == global_state.base_mint,
+        mint::token_program = token_program
+    )]
+    pub base_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = ve_mint.key() == global_state.ve_mint,
+        mint::token_program = ve_token_program
+    )]
+    pub ve_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [VE_ESCROW_SEED, user_lock.key().as_ref()],
+        bump
+    )]
+    pub ve_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.key() == reward_token.vault
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_VAULT_SEED, global_state.base_mint.as_ref()],
+        bump,
+        constraint = token_vault.key() == global_state.token_vault
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            base_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Box<Account<'info, FeeEpoch>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub ve_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<CompoundFees>) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.paused && !ctx.accounts.global_state.emergency_shutdown,
+        ErrorCode::ProtocolPaused
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let user_lock_key = ctx.accounts.user_lock.key();
+    let global_state_key = ctx.accounts.global_state.key();
+
+    require!(ctx.accounts.user_lock.locked_amount > 0, ErrorCode::NoExistingLock);
+    require!(current_time < ctx.accounts.user_lock.unlock_time, ErrorCode::LockExpired);
+
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
+    let user_reward = &mut ctx.accounts.user_reward;
+    write_user_reward(
+        user_reward,
+        user_lock_key,
+        ctx.accounts.base_mint.key(),
+        ctx.accounts.reward_token.start_epoch,
+        ctx.bumps.user_reward,
+    );
+
+    // Accrue against the current decay line before the top-up replaces it
+    let pending_fees = accrue_epoch_rewards(
+        &ctx.accounts.user_lock,
+        &user_lock_key,
+        user_reward,
+        &ctx.accounts.global_state.base_mint,
+        ctx.remaining_accounts,
+        current_time,
+        ctx.program_id,
+    )?;
+    require!(pending_fees > 0, ErrorCode::NoFeesToClaim);
+
+    let user_lock = &mut ctx.accounts.user_lock;
+    record_lock_point(
+        &mut ctx.accounts.lock_point,
+        user_lock_key,
+        user_lock,
+        ctx.bumps.lock_point,
+        current_time,
+    )?;
+
+    let old_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+
+    let remaining_duration = user_lock
+        .unlock_time
+        .checked_sub(current_time)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let time_multiplier = calculate_time_multiplier(
+        remaining_duration,
+        ctx.accounts.global_state.min_lock_duration,
+        ctx.accounts.global_state.max_lock_duration,
+        user_lock.lock_multiplier_numerator,
+        user_lock.lock_multiplier_denominator,
+    )?;
+
+    let additional_ve_amount = pending_fees
+        .checked_mul(time_multiplier)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(user_lock.lock_multiplier_denominator)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let global_state_bump = ctx.accounts.global_state.bump;
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+    let seeds = &[GLOBAL_STATE_SEED, base_mint_key.as_ref(), &[global_state_bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    pay_from_fee_vault(
+        &ctx.accounts.global_state,
+        &ctx.accounts.fee_vault,
+        &ctx.accounts.base_mint,
+        ctx.accounts.token_vault.to_account_info(),
+        &ctx.accounts.token_program,
+        pending_fees,
+    )?;
+
+    let mint_accounts = MintTo {
+        mint: ctx.accounts.ve_mint.to_account_info(),
+        to: ctx.accounts.ve_escrow.to_account_info(),
+        authority: ctx.accounts.global_state.to_account_info(),
+    };
+
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.ve_token_program.to_account_info(),
+            mint_accounts,
+            signer_seeds,
+        ),
+        additional_ve_amount,
+    )?;
+
+    user_lock.locked_amount = user_lock
+        .locked_amount
+        .checked_add(pending_fees)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    user_lock.initial_ve_amount = user_lock
+        .initial_ve_amount
+        .checked_add(additional_ve_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let new_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+
+    let global_state = &mut ctx.accounts.global_state;
+    update_ve_supply(
+        global_state,
+        &mut ctx.accounts.slope_schedule,
+        old_point,
+        user_lock.unlock_time,
+        new_point,
+        user_lock.unlock_time,
+    )?;
+
+    global_state.total_locked = global_state
+        .total_locked
+        .checked_add(pending_fees)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    global_state.total_ve_supply = global_state
+        .total_ve_supply
+        .checked_add(additional_ve_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    msg!("Compounded {} fees into the lock", pending_fees);
+    msg!("Minted {} additional veTokens", additional_ve_amount);
+
+    Ok(())
+}
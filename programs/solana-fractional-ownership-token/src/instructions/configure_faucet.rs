@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct ConfigureFaucet<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == global_state.authority
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + std::mem::size_of::<FaucetState>(),
+        seeds = [FAUCET_STATE_SEED, global_state.base_mint.as_ref()],
+        bump
+    )]
+    pub faucet_state: Account<'info, FaucetState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<ConfigureFaucet>,
+    max_mint_amount: u64,
+    cooldown: i64,
+    total_cap: u64,
+    allowlist: Vec<Pubkey>,
+) -> Result<()> {
+    require!(
+        max_mint_amount > 0 && total_cap > 0 && cooldown >= 0,
+        ErrorCode::InvalidAmount
+    );
+    require!(
+        allowlist.len() <= MAX_FAUCET_ALLOWLIST,
+        ErrorCode::FaucetAllowlistTooLong
+    );
+
+    let faucet_state = &mut ctx.accounts.faucet_state;
+
+    faucet_state.global_state = ctx.accounts.global_state.key();
+    faucet_state.max_mint_amount = max_mint_amount;
+    faucet_state.cooldown = cooldown;
+    faucet_state.total_cap = total_cap;
+    faucet_state.allowlist = [Pubkey::default(); MAX_FAUCET_ALLOWLIST];
+    faucet_state.allowlist[..allowlist.len()].copy_from_slice(&allowlist);
+    faucet_state.bump = ctx.bumps.faucet_state;
+
+    msg!("Faucet configured: {} per mint, {}s cooldown, {} cap", max_mint_amount, cooldown, total_cap);
+
+    Ok(())
+}
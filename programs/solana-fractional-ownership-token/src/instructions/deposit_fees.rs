@@ -1,93 +1,199 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token_2022::{self, Token2022, TransferChecked},
-    token_interface::{Mint, TokenAccount},
+    token_interface::{self, Burn, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
 use crate::constants::*;
 use crate::error::ErrorCode;
 use crate::state::*;
+use crate::utils::{
+    bps_share, credit_fee_epoch, sync_fee_epoch, week_start,
+};
 
 #[derive(Accounts)]
 pub struct DepositFees<'info> {
+    pub depositor: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
     #[account(
         mut,
-        constraint = authority.key() == global_state.authority
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
     )]
-    pub authority: Signer<'info>,
+    pub global_state: Account<'info, GlobalState>,
 
     #[account(
         mut,
-        seeds = [GLOBAL_STATE_SEED],
-        bump = global_state.bump
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
     )]
-    pub global_state: Account<'info, GlobalState>,
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
 
     #[account(
         mut,
-        constraint = base_mint.key() == global_state.base_mint
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), reward_mint.key().as_ref()],
+        bump = reward_token.bump
     )]
-    pub base_mint: InterfaceAccount<'info, Mint>,
+    pub reward_token: Account<'info, RewardToken>,
 
     #[account(
         mut,
-        associated_token::mint = base_mint,
-        associated_token::authority = authority,
-        associated_token::token_program = token_program
+        mint::token_program = token_program
     )]
-    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub reward_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        seeds = [FEE_VAULT_SEED],
-        bump,
-        constraint = fee_vault.key() == global_state.fee_vault
+        token::mint = reward_mint,
+        token::authority = depositor,
+        token::token_program = token_program
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.key() == reward_token.vault
     )]
     pub fee_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token2022>,
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::token_program = token_program,
+        constraint = treasury_token_account.owner == global_state.treasury
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            reward_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Account<'info, FeeEpoch>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<DepositFees>, amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.paused && !ctx.accounts.global_state.emergency_shutdown,
+        ErrorCode::ProtocolPaused
+    );
     require!(amount > 0, ErrorCode::InvalidAmount);
 
-    let global_state = &mut ctx.accounts.global_state;
-
-    let transfer_accounts = TransferChecked {
-        from: ctx.accounts.authority_token_account.to_account_info(),
-        mint: ctx.accounts.base_mint.to_account_info(),
-        to: ctx.accounts.fee_vault.to_account_info(),
-        authority: ctx.accounts.authority.to_account_info(),
-    };
-
-    token_2022::transfer_checked(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            transfer_accounts,
-        ),
-        amount,
-        ctx.accounts.base_mint.decimals,
+    let current_time = Clock::get()?.unix_timestamp;
+    let global_state_key = ctx.accounts.global_state.key();
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
     )?;
 
-    global_state.total_fees_deposited = global_state
-        .total_fees_deposited
-        .checked_add(amount)
+    // Take the treasury and burn shares before anything reaches ve holders
+    let global_state = &ctx.accounts.global_state;
+    let treasury_amount = bps_share(amount, global_state.treasury_fee_bps)?;
+    let burn_amount = bps_share(amount, global_state.burn_fee_bps)?;
+    let distributed_amount = amount
+        .checked_sub(treasury_amount)
+        .and_then(|a| a.checked_sub(burn_amount))
         .ok_or(ErrorCode::MathOverflow)?;
 
-    if global_state.total_ve_supply > 0 {
-        let fee_per_ve_token = (amount as u128)
-            .checked_mul(1_000_000_000_000_000_000)
-            .ok_or(ErrorCode::MathOverflow)?
-            .checked_div(global_state.total_ve_supply as u128)
-            .ok_or(ErrorCode::MathOverflow)?;
-
-        global_state.cumulative_fee_per_ve_token = global_state
-            .cumulative_fee_per_ve_token
-            .checked_add(fee_per_ve_token)
-            .ok_or(ErrorCode::MathOverflow)?;
+    if treasury_amount > 0 {
+        let treasury_token_account = ctx
+            .accounts
+            .treasury_token_account
+            .as_ref()
+            .ok_or(ErrorCode::MissingTreasuryAccount)?;
+
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.depositor_token_account.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: treasury_token_account.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        };
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+            ),
+            treasury_amount,
+            ctx.accounts.reward_mint.decimals,
+        )?;
+    }
+
+    if burn_amount > 0 {
+        let burn_accounts = Burn {
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            from: ctx.accounts.depositor_token_account.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        };
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                burn_accounts,
+            ),
+            burn_amount,
+        )?;
     }
 
-    msg!("Deposited {} fees to vault", amount);
+    if distributed_amount > 0 {
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.depositor_token_account.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.fee_vault.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        };
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+            ),
+            distributed_amount,
+            ctx.accounts.reward_mint.decimals,
+        )?;
+    }
+
+    let reward_token = &mut ctx.accounts.reward_token;
+    reward_token.total_deposited = reward_token
+        .total_deposited
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    reward_token.total_treasury_fees = reward_token
+        .total_treasury_fees
+        .checked_add(treasury_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    reward_token.total_burned = reward_token
+        .total_burned
+        .checked_add(burn_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let fee_epoch = &mut ctx.accounts.fee_epoch;
+    credit_fee_epoch(reward_token, fee_epoch, distributed_amount)?;
+
+    msg!(
+        "Deposited {} {} fees into epoch {} ({} to treasury, {} burned)",
+        distributed_amount,
+        ctx.accounts.reward_mint.key(),
+        fee_epoch.epoch_start,
+        treasury_amount,
+        burn_amount
+    );
 
     Ok(())
 }
@@ -0,0 +1,275 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, Burn},
+};
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::{
+    calculate_ve_point, defer_fees, early_withdraw_penalty, record_lock_point, sync_fee_epoch,
+    update_ve_supply, week_start,
+};
+
+#[derive(Accounts)]
+pub struct EarlyWithdraw<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            user_lock.user.as_ref(),
+            &user_lock.position_id.to_le_bytes()
+        ],
+        bump = user_lock.bump
+    )]
+    pub user_lock: Box<Account<'info, UserLock>>,
+
+    #[account(
+        token::mint = user_lock.position_mint,
+        token::authority = user,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LockPoint>(),
+        seeds = [LOCK_POINT_SEED, user_lock.key().as_ref(), &user_lock.point_count.to_le_bytes()],
+        bump
+    )]
+    pub lock_point: Box<Account<'info, LockPoint>>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), base_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Box<Account<'info, RewardToken>>,
+
+    #[account(
+        constraint = base_mint.key() == global_state.base_mint,
+        mint::token_program = token_program
+    )]
+    pub base_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = ve_mint.key() == global_state.ve_mint,
+        mint::token_program = ve_token_program
+    )]
+    pub ve_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = base_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [VE_ESCROW_SEED, user_lock.key().as_ref()],
+        bump
+    )]
+    pub ve_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_VAULT_SEED, global_state.base_mint.as_ref()],
+        bump,
+        constraint = token_vault.key() == global_state.token_vault
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.key() == reward_token.vault
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            base_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Box<Account<'info, FeeEpoch>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub ve_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<EarlyWithdraw>) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.paused && !ctx.accounts.global_state.emergency_shutdown,
+        ErrorCode::ProtocolPaused
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let global_state_key = ctx.accounts.global_state.key();
+
+    require!(ctx.accounts.user_lock.locked_amount > 0, ErrorCode::NoExistingLock);
+    // An expired lock leaves through withdraw without a penalty
+    require!(current_time < ctx.accounts.user_lock.unlock_time, ErrorCode::LockExpired);
+
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
+    let user_lock = &mut ctx.accounts.user_lock;
+    record_lock_point(
+        &mut ctx.accounts.lock_point,
+        user_lock.key(),
+        user_lock,
+        ctx.bumps.lock_point,
+        current_time,
+    )?;
+
+    let old_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+    let old_unlock_time = user_lock.unlock_time;
+
+    let amount = user_lock.locked_amount;
+    let ve_amount = user_lock.initial_ve_amount;
+    let penalty = early_withdraw_penalty(
+        amount,
+        old_unlock_time - current_time,
+        &ctx.accounts.global_state,
+    )?;
+    let returned_amount = amount.checked_sub(penalty).ok_or(ErrorCode::MathOverflow)?;
+
+    let global_state_bump = ctx.accounts.global_state.bump;
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+    let seeds = &[GLOBAL_STATE_SEED, base_mint_key.as_ref(), &[global_state_bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let burn_accounts = Burn {
+        mint: ctx.accounts.ve_mint.to_account_info(),
+        from: ctx.accounts.ve_escrow.to_account_info(),
+        authority: ctx.accounts.global_state.to_account_info(),
+    };
+
+    token_interface::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.ve_token_program.to_account_info(),
+            burn_accounts,
+            signer_seeds,
+        ),
+        ve_amount,
+    )?;
+
+    if returned_amount > 0 {
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.token_vault.to_account_info(),
+            mint: ctx.accounts.base_mint.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+                signer_seeds,
+            ),
+            returned_amount,
+            ctx.accounts.base_mint.decimals,
+        )?;
+    }
+
+    // The penalty is paid to remaining lockers like a base-mint fee deposit,
+    // from the next epoch on since the open one still counts the leaver's ve
+    if penalty > 0 {
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.token_vault.to_account_info(),
+            mint: ctx.accounts.base_mint.to_account_info(),
+            to: ctx.accounts.fee_vault.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+                signer_seeds,
+            ),
+            penalty,
+            ctx.accounts.base_mint.decimals,
+        )?;
+    }
+
+    let reward_token = &mut ctx.accounts.reward_token;
+    reward_token.total_penalties = reward_token
+        .total_penalties
+        .checked_add(penalty)
+        .ok_or(ErrorCode::MathOverflow)?;
+    defer_fees(reward_token, penalty, ctx.accounts.fee_epoch.epoch_start)?;
+
+    let global_state = &mut ctx.accounts.global_state;
+    update_ve_supply(
+        global_state,
+        &mut ctx.accounts.slope_schedule,
+        old_point,
+        old_unlock_time,
+        (0, 0),
+        0,
+    )?;
+
+    global_state.total_locked = global_state
+        .total_locked
+        .checked_sub(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    global_state.total_ve_supply = global_state
+        .total_ve_supply
+        .checked_sub(ve_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    user_lock.locked_amount = 0;
+    user_lock.unlock_time = 0;
+    user_lock.lock_start_time = 0;
+    user_lock.initial_ve_amount = 0;
+
+    msg!("Withdrew {} tokens early", returned_amount);
+    msg!("Paid {} tokens penalty to lockers", penalty);
+    msg!("Burned {} veTokens", ve_amount);
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::calculate_current_ve_balance;
+
+#[derive(Accounts)]
+pub struct GetVotingPower<'info> {
+    #[account(
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+/// Sums the decayed ve balance of the positions whose NFT `holder` owns,
+/// passed in `remaining_accounts` as `[user_lock, position_token_account]`
+/// pairs in ascending `user_lock` address order.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, GetVotingPower<'info>>,
+    holder: Pubkey,
+) -> Result<u64> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+
+    let mut voting_power: u64 = 0;
+    let mut previous_key = Pubkey::default();
+
+    let pairs = ctx.remaining_accounts.chunks_exact(2);
+    require!(pairs.remainder().is_empty(), ErrorCode::InvalidPosition);
+
+    for pair in pairs {
+        let user_lock_info = &pair[0];
+        // Strictly ascending addresses keep a position from being counted twice
+        require!(user_lock_info.key() > previous_key, ErrorCode::InvalidPosition);
+        previous_key = user_lock_info.key();
+
+        let user_lock = Account::<UserLock>::try_from(user_lock_info)?;
+        let expected_key = Pubkey::create_program_address(
+            &[
+                USER_LOCK_SEED,
+                base_mint_key.as_ref(),
+                user_lock.user.as_ref(),
+                &user_lock.position_id.to_le_bytes(),
+                &[user_lock.bump],
+            ],
+            ctx.program_id,
+        )
+        .map_err(|_| ErrorCode::InvalidPosition)?;
+        require_keys_eq!(user_lock_info.key(), expected_key, ErrorCode::InvalidPosition);
+
+        let position = InterfaceAccount::<TokenAccount>::try_from(&pair[1])?;
+        require_keys_eq!(position.mint, user_lock.position_mint, ErrorCode::InvalidPosition);
+        require_keys_eq!(position.owner, holder, ErrorCode::InvalidPosition);
+        require!(position.amount == 1, ErrorCode::InvalidPosition);
+
+        let ve_balance = calculate_current_ve_balance(
+            user_lock.initial_ve_amount,
+            user_lock.lock_start_time,
+            user_lock.unlock_time,
+            current_time,
+        )?;
+        voting_power = voting_power
+            .checked_add(ve_balance)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    msg!("Voting power of {}: {}", holder, voting_power);
+
+    Ok(voting_power)
+}
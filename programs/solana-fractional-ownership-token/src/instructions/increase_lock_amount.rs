@@ -1,13 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token_2022::{self, Token2022, TransferChecked, MintTo},
-    token_interface::{Mint, TokenAccount},
+    token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, MintTo},
 };
 
 use crate::constants::*;
 use crate::error::ErrorCode;
 use crate::state::*;
-use crate::utils::calculate_time_multiplier;
+use crate::utils::{
+    calculate_time_multiplier, calculate_ve_point, record_lock_point, sync_fee_epoch,
+    update_ve_supply, week_start,
+};
 
 #[derive(Accounts)]
 pub struct IncreaseLockAmount<'info> {
@@ -16,27 +18,78 @@ pub struct IncreaseLockAmount<'info> {
 
     #[account(
         mut,
-        seeds = [USER_LOCK_SEED, user.key().as_ref()],
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            user_lock.user.as_ref(),
+            &user_lock.position_id.to_le_bytes()
+        ],
         bump = user_lock.bump
     )]
     pub user_lock: Account<'info, UserLock>,
 
+    #[account(
+        token::mint = user_lock.position_mint,
+        token::authority = user,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LockPoint>(),
+        seeds = [LOCK_POINT_SEED, user_lock.key().as_ref(), &user_lock.point_count.to_le_bytes()],
+        bump
+    )]
+    pub lock_point: Account<'info, LockPoint>,
+
     #[account(
         mut,
-        seeds = [GLOBAL_STATE_SEED],
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
         bump = global_state.bump
     )]
     pub global_state: Account<'info, GlobalState>,
 
     #[account(
         mut,
-        constraint = base_mint.key() == global_state.base_mint
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), base_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Box<Account<'info, RewardToken>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            base_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Box<Account<'info, FeeEpoch>>,
+
+    #[account(
+        mut,
+        constraint = base_mint.key() == global_state.base_mint,
+        mint::token_program = token_program
     )]
     pub base_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        constraint = ve_mint.key() == global_state.ve_mint
+        constraint = ve_mint.key() == global_state.ve_mint,
+        mint::token_program = ve_token_program
     )]
     pub ve_mint: InterfaceAccount<'info, Mint>,
 
@@ -50,32 +103,53 @@ pub struct IncreaseLockAmount<'info> {
 
     #[account(
         mut,
-        associated_token::mint = ve_mint,
-        associated_token::authority = user,
-        associated_token::token_program = token_program
+        seeds = [VE_ESCROW_SEED, user_lock.key().as_ref()],
+        bump
     )]
-    pub user_ve_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub ve_escrow: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [TOKEN_VAULT_SEED],
+        seeds = [TOKEN_VAULT_SEED, global_state.base_mint.as_ref()],
         bump,
         constraint = token_vault.key() == global_state.token_vault
     )]
     pub token_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token2022>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub ve_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<IncreaseLockAmount>, additional_amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.paused && !ctx.accounts.global_state.emergency_shutdown,
+        ErrorCode::ProtocolPaused
+    );
     require!(additional_amount > 0, ErrorCode::InvalidAmount);
 
     let current_time = Clock::get()?.unix_timestamp;
+    let global_state_key = ctx.accounts.global_state.key();
     let user_lock = &mut ctx.accounts.user_lock;
 
     require!(user_lock.locked_amount > 0, ErrorCode::NoExistingLock);
     require!(current_time < user_lock.unlock_time, ErrorCode::LockExpired);
 
+    record_lock_point(
+        &mut ctx.accounts.lock_point,
+        user_lock.key(),
+        user_lock,
+        ctx.bumps.lock_point,
+        current_time,
+    )?;
+
+    let old_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+
     let remaining_duration = user_lock
         .unlock_time
         .checked_sub(current_time)
@@ -83,14 +157,16 @@ pub fn handler(ctx: Context<IncreaseLockAmount>, additional_amount: u64) -> Resu
 
     let time_multiplier = calculate_time_multiplier(
         remaining_duration,
-        ctx.accounts.global_state.lock_multiplier_numerator,
-        ctx.accounts.global_state.lock_multiplier_denominator,
+        ctx.accounts.global_state.min_lock_duration,
+        ctx.accounts.global_state.max_lock_duration,
+        user_lock.lock_multiplier_numerator,
+        user_lock.lock_multiplier_denominator,
     )?;
 
     let additional_ve_amount = additional_amount
         .checked_mul(time_multiplier)
         .ok_or(ErrorCode::MathOverflow)?
-        .checked_div(ctx.accounts.global_state.lock_multiplier_denominator)
+        .checked_div(user_lock.lock_multiplier_denominator)
         .ok_or(ErrorCode::MathOverflow)?;
 
     let global_state_bump = ctx.accounts.global_state.bump;
@@ -102,7 +178,7 @@ pub fn handler(ctx: Context<IncreaseLockAmount>, additional_amount: u64) -> Resu
         authority: ctx.accounts.user.to_account_info(),
     };
 
-    token_2022::transfer_checked(
+    token_interface::transfer_checked(
         CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             transfer_accounts,
@@ -111,18 +187,19 @@ pub fn handler(ctx: Context<IncreaseLockAmount>, additional_amount: u64) -> Resu
         ctx.accounts.base_mint.decimals,
     )?;
 
-    let seeds = &[GLOBAL_STATE_SEED, &[global_state_bump]];
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+    let seeds = &[GLOBAL_STATE_SEED, base_mint_key.as_ref(), &[global_state_bump]];
     let signer_seeds = &[&seeds[..]];
 
     let mint_accounts = MintTo {
         mint: ctx.accounts.ve_mint.to_account_info(),
-        to: ctx.accounts.user_ve_token_account.to_account_info(),
+        to: ctx.accounts.ve_escrow.to_account_info(),
         authority: ctx.accounts.global_state.to_account_info(),
     };
 
-    token_2022::mint_to(
+    token_interface::mint_to(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.ve_token_program.to_account_info(),
             mint_accounts,
             signer_seeds,
         ),
@@ -139,7 +216,34 @@ pub fn handler(ctx: Context<IncreaseLockAmount>, additional_amount: u64) -> Resu
         .checked_add(additional_ve_amount)
         .ok_or(ErrorCode::MathOverflow)?;
 
+    let new_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+
+    // Lock changes advance the base mint's stream before the supply moves
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
     let global_state = &mut ctx.accounts.global_state;
+    update_ve_supply(
+        global_state,
+        &mut ctx.accounts.slope_schedule,
+        old_point,
+        user_lock.unlock_time,
+        new_point,
+        user_lock.unlock_time,
+    )?;
+
     global_state.total_locked = global_state
         .total_locked
         .checked_add(additional_amount)
@@ -1,12 +1,12 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token_2022::Token2022,
-    token_interface::{Mint, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
 use crate::constants::*;
 use crate::state::*;
+use crate::instructions::register_reward_mint::write_reward_token;
+use crate::utils::{validate_lock_config, week_start};
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
@@ -17,7 +17,7 @@ pub struct Initialize<'info> {
         init,
         payer = authority,
         space = 8 + std::mem::size_of::<GlobalState>(),
-        seeds = [GLOBAL_STATE_SEED],
+        seeds = [GLOBAL_STATE_SEED, base_mint.key().as_ref()],
         bump
     )]
     pub global_state: Account<'info, GlobalState>,
@@ -46,7 +46,7 @@ pub struct Initialize<'info> {
         token::mint = base_mint,
         token::authority = global_state,
         token::token_program = token_program,
-        seeds = [TOKEN_VAULT_SEED],
+        seeds = [TOKEN_VAULT_SEED, base_mint.key().as_ref()],
         bump
     )]
     pub token_vault: InterfaceAccount<'info, TokenAccount>,
@@ -57,12 +57,30 @@ pub struct Initialize<'info> {
         token::mint = base_mint,
         token::authority = global_state,
         token::token_program = token_program,
-        seeds = [FEE_VAULT_SEED],
+        seeds = [FEE_VAULT_SEED, base_mint.key().as_ref(), base_mint.key().as_ref()],
         bump
     )]
     pub fee_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token2022>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<SlopeSchedule>(),
+        seeds = [SLOPE_SCHEDULE_SEED, base_mint.key().as_ref()],
+        bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<RewardToken>(),
+        seeds = [REWARD_TOKEN_SEED, base_mint.key().as_ref(), base_mint.key().as_ref()],
+        bump
+    )]
+    pub reward_token: Box<Account<'info, RewardToken>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
@@ -70,21 +88,44 @@ pub fn handler(
     ctx: Context<Initialize>,
     lock_multiplier_numerator: u64,
     lock_multiplier_denominator: u64,
+    max_lock_multiplier: u64,
+    min_lock_duration: i64,
+    max_lock_duration: i64,
 ) -> Result<()> {
-    let global_state = &mut ctx.accounts.global_state;
+    validate_lock_config(
+        lock_multiplier_numerator,
+        lock_multiplier_denominator,
+        max_lock_multiplier,
+        min_lock_duration,
+        max_lock_duration,
+    )?;
 
-    global_state.authority = ctx.accounts.authority.key();
-    global_state.base_mint = ctx.accounts.base_mint.key();
-    global_state.ve_mint = ctx.accounts.ve_mint.key();
-    global_state.token_vault = ctx.accounts.token_vault.key();
-    global_state.fee_vault = ctx.accounts.fee_vault.key();
-    global_state.total_locked = 0;
-    global_state.total_ve_supply = 0;
-    global_state.total_fees_deposited = 0;
-    global_state.cumulative_fee_per_ve_token = 0;
-    global_state.lock_multiplier_numerator = lock_multiplier_numerator;
-    global_state.lock_multiplier_denominator = lock_multiplier_denominator;
-    global_state.bump = ctx.bumps.global_state;
+    write_global_state(
+        &mut ctx.accounts.global_state,
+        ctx.accounts.authority.key(),
+        ctx.accounts.base_mint.key(),
+        ctx.accounts.ve_mint.key(),
+        ctx.accounts.token_vault.key(),
+        ctx.accounts.fee_vault.key(),
+        lock_multiplier_numerator,
+        lock_multiplier_denominator,
+        max_lock_multiplier,
+        min_lock_duration,
+        max_lock_duration,
+        ctx.bumps.global_state,
+    )?;
+
+    let slope_schedule = &mut ctx.accounts.slope_schedule;
+    slope_schedule.global_state = ctx.accounts.global_state.key();
+    slope_schedule.bump = ctx.bumps.slope_schedule;
+
+    write_reward_token(
+        &mut ctx.accounts.reward_token,
+        ctx.accounts.global_state.key(),
+        ctx.accounts.base_mint.key(),
+        ctx.accounts.fee_vault.key(),
+        ctx.bumps.reward_token,
+    )?;
 
     msg!("Protocol initialized");
     msg!("Base mint: {}", ctx.accounts.base_mint.key());
@@ -92,3 +133,55 @@ pub fn handler(
 
     Ok(())
 }
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write_global_state(
+    global_state: &mut GlobalState,
+    authority: Pubkey,
+    base_mint: Pubkey,
+    ve_mint: Pubkey,
+    token_vault: Pubkey,
+    fee_vault: Pubkey,
+    lock_multiplier_numerator: u64,
+    lock_multiplier_denominator: u64,
+    max_lock_multiplier: u64,
+    min_lock_duration: i64,
+    max_lock_duration: i64,
+    bump: u8,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    global_state.authority = authority;
+    global_state.pending_authority = Pubkey::default();
+    global_state.fee_depositor = authority;
+    global_state.pauser = authority;
+    global_state.treasury = authority;
+    global_state.base_mint = base_mint;
+    global_state.ve_mint = ve_mint;
+    global_state.token_vault = token_vault;
+    global_state.fee_vault = fee_vault;
+    global_state.total_locked = 0;
+    global_state.total_ve_supply = 0;
+    global_state.ve_bias = 0;
+    global_state.ve_slope = 0;
+    global_state.ve_checkpoint_time = current_time;
+    global_state.ve_epoch_start = week_start(current_time);
+    global_state.ve_epoch_supply = 0;
+    global_state.lock_multiplier_numerator = lock_multiplier_numerator;
+    global_state.lock_multiplier_denominator = lock_multiplier_denominator;
+    global_state.pending_lock_multiplier_numerator = 0;
+    global_state.pending_lock_multiplier_denominator = 0;
+    global_state.lock_multiplier_effective_at = 0;
+    global_state.min_lock_duration = min_lock_duration;
+    global_state.max_lock_duration = max_lock_duration;
+    global_state.max_lock_multiplier = max_lock_multiplier;
+    global_state.treasury_fee_bps = 0;
+    global_state.burn_fee_bps = 0;
+    global_state.early_withdraw_penalty_bps = DEFAULT_EARLY_WITHDRAW_PENALTY_BPS;
+    global_state.early_withdraw_penalty_cap_bps = DEFAULT_EARLY_WITHDRAW_PENALTY_CAP_BPS;
+    global_state.paused = false;
+    global_state.emergency_shutdown = false;
+    global_state.bump = bump;
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::validate_lock_config;
+use crate::instructions::initialize::write_global_state;
+use crate::instructions::register_reward_mint::write_reward_token;
+
+#[derive(Accounts)]
+pub struct InitializeWithMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<GlobalState>(),
+        seeds = [GLOBAL_STATE_SEED, base_mint.key().as_ref()],
+        bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    // Existing mint; its authority stays with the issuer, who must sign so
+    // nobody else can claim the instance for it first
+    #[account(
+        mint::token_program = token_program,
+        constraint = base_mint.mint_authority == Some(authority.key()).into() @ ErrorCode::NotMintAuthority
+    )]
+    pub base_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = base_mint.decimals,
+        mint::authority = global_state,
+        mint::token_program = ve_token_program,
+    )]
+    pub ve_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = base_mint,
+        token::authority = global_state,
+        token::token_program = token_program,
+        seeds = [TOKEN_VAULT_SEED, base_mint.key().as_ref()],
+        bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = base_mint,
+        token::authority = global_state,
+        token::token_program = token_program,
+        seeds = [FEE_VAULT_SEED, base_mint.key().as_ref(), base_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<SlopeSchedule>(),
+        seeds = [SLOPE_SCHEDULE_SEED, base_mint.key().as_ref()],
+        bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<RewardToken>(),
+        seeds = [REWARD_TOKEN_SEED, base_mint.key().as_ref(), base_mint.key().as_ref()],
+        bump
+    )]
+    pub reward_token: Box<Account<'info, RewardToken>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub ve_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<InitializeWithMint>,
+    lock_multiplier_numerator: u64,
+    lock_multiplier_denominator: u64,
+    max_lock_multiplier: u64,
+    min_lock_duration: i64,
+    max_lock_duration: i64,
+) -> Result<()> {
+    validate_lock_config(
+        lock_multiplier_numerator,
+        lock_multiplier_denominator,
+        max_lock_multiplier,
+        min_lock_duration,
+        max_lock_duration,
+    )?;
+
+    write_global_state(
+        &mut ctx.accounts.global_state,
+        ctx.accounts.authority.key(),
+        ctx.accounts.base_mint.key(),
+        ctx.accounts.ve_mint.key(),
+        ctx.accounts.token_vault.key(),
+        ctx.accounts.fee_vault.key(),
+        lock_multiplier_numerator,
+        lock_multiplier_denominator,
+        max_lock_multiplier,
+        min_lock_duration,
+        max_lock_duration,
+        ctx.bumps.global_state,
+    )?;
+
+    let slope_schedule = &mut ctx.accounts.slope_schedule;
+    slope_schedule.global_state = ctx.accounts.global_state.key();
+    slope_schedule.bump = ctx.bumps.slope_schedule;
+
+    write_reward_token(
+        &mut ctx.accounts.reward_token,
+        ctx.accounts.global_state.key(),
+        ctx.accounts.base_mint.key(),
+        ctx.accounts.fee_vault.key(),
+        ctx.bumps.reward_token,
+    )?;
+
+    msg!("Protocol initialized with existing mint");
+    msg!("Base mint: {} ({} decimals)", ctx.accounts.base_mint.key(), ctx.accounts.base_mint.decimals);
+    msg!("VeToken mint: {}", ctx.accounts.ve_mint.key());
+
+    Ok(())
+}
@@ -1,16 +1,20 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_2022::{self, Token2022, TransferChecked, MintTo},
-    token_interface::{Mint, TokenAccount},
+    token_2022::Token2022,
+    token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, MintTo},
 };
 
 use crate::constants::*;
 use crate::error::ErrorCode;
 use crate::state::*;
-use crate::utils::calculate_time_multiplier;
+use crate::utils::{
+    calculate_time_multiplier, calculate_ve_point, record_lock_point, round_up_to_week,
+    sync_fee_epoch, update_ve_supply, week_start,
+};
 
 #[derive(Accounts)]
+#[instruction(position_id: u64)]
 pub struct LockTokens<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
@@ -19,27 +23,86 @@ pub struct LockTokens<'info> {
         init_if_needed,
         payer = user,
         space = 8 + std::mem::size_of::<UserLock>(),
-        seeds = [USER_LOCK_SEED, user.key().as_ref()],
+        seeds = [USER_LOCK_SEED, global_state.base_mint.as_ref(), user.key().as_ref(), &position_id.to_le_bytes()],
         bump
     )]
     pub user_lock: Account<'info, UserLock>,
 
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LockPoint>(),
+        seeds = [LOCK_POINT_SEED, user_lock.key().as_ref(), &user_lock.point_count.to_le_bytes()],
+        bump
+    )]
+    pub lock_point: Account<'info, LockPoint>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [POSITION_MINT_SEED, user_lock.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = global_state,
+        mint::token_program = position_token_program
+    )]
+    pub position_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = position_mint,
+        associated_token::authority = user,
+        associated_token::token_program = position_token_program
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
     #[account(
         mut,
-        seeds = [GLOBAL_STATE_SEED],
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
         bump = global_state.bump
     )]
     pub global_state: Account<'info, GlobalState>,
 
     #[account(
         mut,
-        constraint = base_mint.key() == global_state.base_mint
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), base_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Box<Account<'info, RewardToken>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            base_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Box<Account<'info, FeeEpoch>>,
+
+    #[account(
+        mut,
+        constraint = base_mint.key() == global_state.base_mint,
+        mint::token_program = token_program
     )]
     pub base_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        constraint = ve_mint.key() == global_state.ve_mint
+        constraint = ve_mint.key() == global_state.ve_mint,
+        mint::token_program = ve_token_program
     )]
     pub ve_mint: InterfaceAccount<'info, Mint>,
 
@@ -54,58 +117,90 @@ pub struct LockTokens<'info> {
     #[account(
         init_if_needed,
         payer = user,
-        associated_token::mint = ve_mint,
-        associated_token::authority = user,
-        associated_token::token_program = token_program
+        token::mint = ve_mint,
+        token::authority = global_state,
+        token::token_program = ve_token_program,
+        seeds = [VE_ESCROW_SEED, user_lock.key().as_ref()],
+        bump
     )]
-    pub user_ve_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub ve_escrow: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [TOKEN_VAULT_SEED],
+        seeds = [TOKEN_VAULT_SEED, global_state.base_mint.as_ref()],
         bump,
         constraint = token_vault.key() == global_state.token_vault
     )]
     pub token_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token2022>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub ve_token_program: Interface<'info, TokenInterface>,
+    pub position_token_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<LockTokens>, amount: u64, lock_duration: i64) -> Result<()> {
+pub fn handler(ctx: Context<LockTokens>, position_id: u64, amount: u64, lock_duration: i64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.paused && !ctx.accounts.global_state.emergency_shutdown,
+        ErrorCode::ProtocolPaused
+    );
     require!(amount > 0, ErrorCode::InvalidAmount);
     require!(
-        lock_duration >= MIN_LOCK_DURATION && lock_duration <= MAX_LOCK_DURATION,
+        (ctx.accounts.global_state.min_lock_duration..=ctx.accounts.global_state.max_lock_duration)
+            .contains(&lock_duration),
         ErrorCode::InvalidLockDuration
     );
 
     let current_time = Clock::get()?.unix_timestamp;
+    let global_state_key = ctx.accounts.global_state.key();
 
     let user_lock = &mut ctx.accounts.user_lock;
-    let is_new_lock = user_lock.locked_amount == 0;
-
-    if is_new_lock {
-        user_lock.user = ctx.accounts.user.key();
-        user_lock.locked_amount = 0;
-        user_lock.unlock_time = 0;
-        user_lock.lock_start_time = 0;
-        user_lock.initial_ve_amount = 0;
-        user_lock.fees_claimed = 0;
-        user_lock.fee_debt = ctx.accounts.global_state.cumulative_fee_per_ve_token;
-        user_lock.bump = ctx.bumps.user_lock;
-    }
+
+    // Each position holds one lock; top-ups go through increase_lock_amount
+    require!(user_lock.locked_amount == 0, ErrorCode::PositionInUse);
+
+    user_lock.user = ctx.accounts.user.key();
+    user_lock.position_id = position_id;
+    user_lock.position_mint = ctx.accounts.position_mint.key();
+    user_lock.delegate = Pubkey::default();
+    user_lock.fee_recipient = ctx.accounts.user.key();
+    user_lock.delegated_by = ctx.accounts.user.key();
+    user_lock.unlock_time = 0;
+    user_lock.lock_start_time = 0;
+    user_lock.initial_ve_amount = 0;
+    user_lock.lock_multiplier_numerator = ctx.accounts.global_state.lock_multiplier_numerator;
+    user_lock.lock_multiplier_denominator = ctx.accounts.global_state.lock_multiplier_denominator;
+    user_lock.bump = ctx.bumps.user_lock;
+
+    record_lock_point(
+        &mut ctx.accounts.lock_point,
+        user_lock.key(),
+        user_lock,
+        ctx.bumps.lock_point,
+        current_time,
+    )?;
+
+    let old_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+    let old_unlock_time = user_lock.unlock_time;
 
     let time_multiplier = calculate_time_multiplier(
         lock_duration,
-        ctx.accounts.global_state.lock_multiplier_numerator,
-        ctx.accounts.global_state.lock_multiplier_denominator,
+        ctx.accounts.global_state.min_lock_duration,
+        ctx.accounts.global_state.max_lock_duration,
+        user_lock.lock_multiplier_numerator,
+        user_lock.lock_multiplier_denominator,
     )?;
 
     let new_ve_amount = amount
         .checked_mul(time_multiplier)
         .ok_or(ErrorCode::MathOverflow)?
-        .checked_div(ctx.accounts.global_state.lock_multiplier_denominator)
+        .checked_div(user_lock.lock_multiplier_denominator)
         .ok_or(ErrorCode::MathOverflow)?;
 
     let global_state_bump = ctx.accounts.global_state.bump;
@@ -117,7 +212,7 @@ pub fn handler(ctx: Context<LockTokens>, amount: u64, lock_duration: i64) -> Res
         authority: ctx.accounts.user.to_account_info(),
     };
 
-    token_2022::transfer_checked(
+    token_interface::transfer_checked(
         CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             transfer_accounts,
@@ -126,48 +221,50 @@ pub fn handler(ctx: Context<LockTokens>, amount: u64, lock_duration: i64) -> Res
         ctx.accounts.base_mint.decimals,
     )?;
 
-    let seeds = &[GLOBAL_STATE_SEED, &[global_state_bump]];
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+    let seeds = &[GLOBAL_STATE_SEED, base_mint_key.as_ref(), &[global_state_bump]];
     let signer_seeds = &[&seeds[..]];
 
     let mint_accounts = MintTo {
         mint: ctx.accounts.ve_mint.to_account_info(),
-        to: ctx.accounts.user_ve_token_account.to_account_info(),
+        to: ctx.accounts.ve_escrow.to_account_info(),
         authority: ctx.accounts.global_state.to_account_info(),
     };
 
-    token_2022::mint_to(
+    token_interface::mint_to(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.ve_token_program.to_account_info(),
             mint_accounts,
             signer_seeds,
         ),
         new_ve_amount,
     )?;
 
+    // A fresh position mints its NFT; a withdrawn one is reopened by its holder
+    if ctx.accounts.position_mint.supply == 0 {
+        let nft_accounts = MintTo {
+            mint: ctx.accounts.position_mint.to_account_info(),
+            to: ctx.accounts.position_token_account.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.position_token_program.to_account_info(),
+                nft_accounts,
+                signer_seeds,
+            ),
+            1,
+        )?;
+    } else {
+        require!(ctx.accounts.position_token_account.amount == 1, ErrorCode::NotPositionHolder);
+    }
+
     let new_unlock_time = current_time
         .checked_add(lock_duration)
         .ok_or(ErrorCode::MathOverflow)?;
 
-    // Calculate weighted average unlock time if adding to existing lock
-    let final_unlock_time = if is_new_lock {
-        new_unlock_time
-    } else {
-        // Use u128 to avoid overflow when multiplying amounts by timestamps
-        let old_weight = (user_lock.locked_amount as u128)
-            .checked_mul(user_lock.unlock_time as u128)
-            .ok_or(ErrorCode::MathOverflow)?;
-        let new_weight = (amount as u128)
-            .checked_mul(new_unlock_time as u128)
-            .ok_or(ErrorCode::MathOverflow)?;
-        let total_weight = old_weight
-            .checked_add(new_weight)
-            .ok_or(ErrorCode::MathOverflow)?;
-        let new_total = (user_lock.locked_amount as u128)
-            .checked_add(amount as u128)
-            .ok_or(ErrorCode::MathOverflow)?;
-
-        (total_weight / new_total) as i64
-    };
+    let final_unlock_time = round_up_to_week(new_unlock_time)?;
 
     // Update user lock state
     user_lock.locked_amount = user_lock.locked_amount
@@ -179,7 +276,34 @@ pub fn handler(ctx: Context<LockTokens>, amount: u64, lock_duration: i64) -> Res
         .checked_add(new_ve_amount)
         .ok_or(ErrorCode::MathOverflow)?;
 
+    let new_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+
+    // Lock changes advance the base mint's stream before the supply moves
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
     let global_state = &mut ctx.accounts.global_state;
+    update_ve_supply(
+        global_state,
+        &mut ctx.accounts.slope_schedule,
+        old_point,
+        old_unlock_time,
+        new_point,
+        user_lock.unlock_time,
+    )?;
+
     global_state.total_locked = global_state
         .total_locked
         .checked_add(amount)
@@ -0,0 +1,469 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, MintTo, Burn, CloseAccount},
+};
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::{
+    accrue_epoch_rewards, calculate_time_multiplier, calculate_ve_point, pay_from_fee_vault,
+    record_lock_point, sync_fee_epoch, update_ve_supply, week_start, write_user_reward,
+};
+
+#[derive(Accounts)]
+pub struct MergeLocks<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            user_lock.user.as_ref(),
+            &user_lock.position_id.to_le_bytes()
+        ],
+        bump = user_lock.bump
+    )]
+    pub user_lock: Box<Account<'info, UserLock>>,
+
+    #[account(
+        token::mint = user_lock.position_mint,
+        token::authority = user,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            source_lock.user.as_ref(),
+            &source_lock.position_id.to_le_bytes()
+        ],
+        bump = source_lock.bump,
+        constraint = source_lock.key() != user_lock.key() @ ErrorCode::InvalidPosition
+    )]
+    pub source_lock: Box<Account<'info, UserLock>>,
+
+    #[account(
+        mut,
+        constraint = source_position_mint.key() == source_lock.position_mint,
+        mint::token_program = position_token_program
+    )]
+    pub source_position_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = source_position_mint,
+        token::authority = user,
+        constraint = source_position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub source_position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LockPoint>(),
+        seeds = [LOCK_POINT_SEED, user_lock.key().as_ref(), &user_lock.point_count.to_le_bytes()],
+        bump
+    )]
+    pub lock_point: Box<Account<'info, LockPoint>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<UserReward>(),
+        seeds = [USER_REWARD_SEED, user_lock.key().as_ref(), reward_mint.key().as_ref()],
+        bump
+    )]
+    pub user_reward: Box<Account<'info, UserReward>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<UserReward>(),
+        seeds = [USER_REWARD_SEED, source_lock.key().as_ref(), reward_mint.key().as_ref()],
+        bump
+    )]
+    pub source_user_reward: Box<Account<'info, UserReward>>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), reward_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Box<Account<'info, RewardToken>>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.key() == reward_token.vault
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            reward_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Box<Account<'info, FeeEpoch>>,
+
+    #[account(
+        mut,
+        constraint = ve_mint.key() == global_state.ve_mint,
+        mint::token_program = ve_token_program
+    )]
+    pub ve_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [VE_ESCROW_SEED, user_lock.key().as_ref()],
+        bump
+    )]
+    pub ve_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [VE_ESCROW_SEED, source_lock.key().as_ref()],
+        bump
+    )]
+    pub source_ve_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub ve_token_program: Interface<'info, TokenInterface>,
+    pub position_token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+/// `remaining_accounts` holds the source's lock points and closed epochs of
+/// `reward_mint` (`source_account_count` accounts, as for `claim_fees`),
+/// followed by the destination's. Every closed epoch of the source must be
+/// settled, since its claim cursor is closed with it.
+pub fn handler(ctx: Context<MergeLocks>, source_account_count: u8) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.paused && !ctx.accounts.global_state.emergency_shutdown,
+        ErrorCode::ProtocolPaused
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let user_lock_key = ctx.accounts.user_lock.key();
+    let source_lock_key = ctx.accounts.source_lock.key();
+    let global_state_key = ctx.accounts.global_state.key();
+    let reward_mint_key = ctx.accounts.reward_mint.key();
+
+    require!(ctx.accounts.user_lock.locked_amount > 0, ErrorCode::NoExistingLock);
+    require!(ctx.accounts.source_lock.locked_amount > 0, ErrorCode::NoExistingLock);
+    require!(current_time < ctx.accounts.user_lock.unlock_time, ErrorCode::LockExpired);
+    require!(current_time < ctx.accounts.source_lock.unlock_time, ErrorCode::LockExpired);
+
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
+    let source_account_count = source_account_count as usize;
+    require!(
+        ctx.remaining_accounts.len() >= source_account_count,
+        ErrorCode::InvalidClaimEntry
+    );
+    let (source_accounts, destination_accounts) = ctx.remaining_accounts.split_at(source_account_count);
+
+    // Settle both positions against their current decay lines before they change
+    let start_epoch = ctx.accounts.reward_token.start_epoch;
+    write_user_reward(
+        &mut ctx.accounts.source_user_reward,
+        source_lock_key,
+        reward_mint_key,
+        start_epoch,
+        ctx.bumps.source_user_reward,
+    );
+    let source_fees = accrue_epoch_rewards(
+        &ctx.accounts.source_lock,
+        &source_lock_key,
+        &mut ctx.accounts.source_user_reward,
+        &ctx.accounts.global_state.base_mint,
+        source_accounts,
+        current_time,
+        ctx.program_id,
+    )?;
+    require!(
+        ctx.accounts.source_user_reward.epoch_cursor >= week_start(current_time),
+        ErrorCode::UnsettledFees
+    );
+
+    write_user_reward(
+        &mut ctx.accounts.user_reward,
+        user_lock_key,
+        reward_mint_key,
+        start_epoch,
+        ctx.bumps.user_reward,
+    );
+    let destination_fees = accrue_epoch_rewards(
+        &ctx.accounts.user_lock,
+        &user_lock_key,
+        &mut ctx.accounts.user_reward,
+        &ctx.accounts.global_state.base_mint,
+        destination_accounts,
+        current_time,
+        ctx.program_id,
+    )?;
+
+    let pending_fees = source_fees
+        .checked_add(destination_fees)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let global_state_bump = ctx.accounts.global_state.bump;
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+    let seeds = &[GLOBAL_STATE_SEED, base_mint_key.as_ref(), &[global_state_bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    if pending_fees > 0 {
+        pay_from_fee_vault(
+            &ctx.accounts.global_state,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.reward_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            pending_fees,
+        )?;
+    }
+
+    let source_lock = &ctx.accounts.source_lock;
+    let source_point = calculate_ve_point(
+        source_lock.initial_ve_amount,
+        source_lock.lock_start_time,
+        source_lock.unlock_time,
+        current_time,
+    )?;
+    let source_unlock_time = source_lock.unlock_time;
+    let source_amount = source_lock.locked_amount;
+    let source_ve_amount = source_lock.initial_ve_amount;
+
+    let user_lock = &mut ctx.accounts.user_lock;
+    record_lock_point(
+        &mut ctx.accounts.lock_point,
+        user_lock_key,
+        user_lock,
+        ctx.bumps.lock_point,
+        current_time,
+    )?;
+
+    let old_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+    let old_unlock_time = user_lock.unlock_time;
+    let old_ve_amount = user_lock
+        .initial_ve_amount
+        .checked_add(source_ve_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // The merged lock restarts now and runs to the later of the two unlock times
+    let new_unlock_time = user_lock.unlock_time.max(source_unlock_time);
+    let new_amount = user_lock
+        .locked_amount
+        .checked_add(source_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let time_multiplier = calculate_time_multiplier(
+        new_unlock_time
+            .checked_sub(current_time)
+            .ok_or(ErrorCode::MathOverflow)?,
+        ctx.accounts.global_state.min_lock_duration,
+        ctx.accounts.global_state.max_lock_duration,
+        user_lock.lock_multiplier_numerator,
+        user_lock.lock_multiplier_denominator,
+    )?;
+
+    let new_ve_amount = new_amount
+        .checked_mul(time_multiplier)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(user_lock.lock_multiplier_denominator)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // The source's ve joins the destination's escrow before the delta is settled
+    if source_ve_amount > 0 {
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.source_ve_escrow.to_account_info(),
+            mint: ctx.accounts.ve_mint.to_account_info(),
+            to: ctx.accounts.ve_escrow.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.ve_token_program.to_account_info(),
+                transfer_accounts,
+                signer_seeds,
+            ),
+            source_ve_amount,
+            ctx.accounts.ve_mint.decimals,
+        )?;
+    }
+
+    if new_ve_amount > old_ve_amount {
+        let additional_ve_amount = new_ve_amount - old_ve_amount;
+
+        let mint_accounts = MintTo {
+            mint: ctx.accounts.ve_mint.to_account_info(),
+            to: ctx.accounts.ve_escrow.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.ve_token_program.to_account_info(),
+                mint_accounts,
+                signer_seeds,
+            ),
+            additional_ve_amount,
+        )?;
+
+        ctx.accounts.global_state.total_ve_supply = ctx
+            .accounts
+            .global_state
+            .total_ve_supply
+            .checked_add(additional_ve_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("Minted {} additional veTokens", additional_ve_amount);
+    } else if new_ve_amount < old_ve_amount {
+        let excess_ve_amount = old_ve_amount - new_ve_amount;
+
+        let burn_accounts = Burn {
+            mint: ctx.accounts.ve_mint.to_account_info(),
+            from: ctx.accounts.ve_escrow.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.ve_token_program.to_account_info(),
+                burn_accounts,
+                signer_seeds,
+            ),
+            excess_ve_amount,
+        )?;
+
+        ctx.accounts.global_state.total_ve_supply = ctx
+            .accounts
+            .global_state
+            .total_ve_supply
+            .checked_sub(excess_ve_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("Burned {} veTokens", excess_ve_amount);
+    }
+
+    // The source position ends with its account, so its escrow and NFT go too
+    let close_accounts = CloseAccount {
+        account: ctx.accounts.source_ve_escrow.to_account_info(),
+        destination: ctx.accounts.user.to_account_info(),
+        authority: ctx.accounts.global_state.to_account_info(),
+    };
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.ve_token_program.to_account_info(),
+        close_accounts,
+        signer_seeds,
+    ))?;
+
+    let nft_burn_accounts = Burn {
+        mint: ctx.accounts.source_position_mint.to_account_info(),
+        from: ctx.accounts.source_position_token_account.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.position_token_program.to_account_info(),
+            nft_burn_accounts,
+        ),
+        1,
+    )?;
+
+    user_lock.locked_amount = new_amount;
+    user_lock.initial_ve_amount = new_ve_amount;
+    user_lock.lock_start_time = current_time;
+    user_lock.unlock_time = new_unlock_time;
+
+    let new_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+
+    let global_state = &mut ctx.accounts.global_state;
+    update_ve_supply(
+        global_state,
+        &mut ctx.accounts.slope_schedule,
+        source_point,
+        source_unlock_time,
+        (0, 0),
+        0,
+    )?;
+    update_ve_supply(
+        global_state,
+        &mut ctx.accounts.slope_schedule,
+        old_point,
+        old_unlock_time,
+        new_point,
+        user_lock.unlock_time,
+    )?;
+
+    // Its cursor is settled and would outlive the lock it belongs to
+    ctx.accounts
+        .source_user_reward
+        .close(ctx.accounts.user.to_account_info())?;
+
+    msg!("Merged {} tokens into the position", source_amount);
+    msg!("Settled {} {} fees", pending_fees, reward_mint_key);
+
+    Ok(())
+}
@@ -1,7 +1,6 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token_2022::{self, Token2022, MintTo},
-    token_interface::{Mint, TokenAccount},
+    token_interface::{self, Mint, TokenAccount, TokenInterface, MintTo},
 };
 
 use crate::constants::*;
@@ -10,33 +9,83 @@ use crate::state::*;
 
 #[derive(Accounts)]
 pub struct MintTokens<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = authority.key() == global_state.authority
+            || faucet_state.allowlist.contains(&authority.key())
+    )]
     pub authority: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [GLOBAL_STATE_SEED],
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
         bump = global_state.bump
     )]
     pub global_state: Account<'info, GlobalState>,
 
     #[account(
         mut,
-        constraint = base_mint.key() == global_state.base_mint
+        seeds = [FAUCET_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = faucet_state.bump
+    )]
+    pub faucet_state: Account<'info, FaucetState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + std::mem::size_of::<FaucetClaim>(),
+        seeds = [FAUCET_CLAIM_SEED, global_state.base_mint.as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub faucet_claim: Account<'info, FaucetClaim>,
+
+    #[account(
+        mut,
+        constraint = base_mint.key() == global_state.base_mint,
+        mint::token_program = token_program
     )]
     pub base_mint: InterfaceAccount<'info, Mint>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        token::mint = base_mint,
+        token::token_program = token_program
+    )]
     pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token2022>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<MintTokens>, amount: u64) -> Result<()> {
     require!(amount > 0, ErrorCode::InvalidAmount);
 
+    let current_time = Clock::get()?.unix_timestamp;
+    let faucet_state = &mut ctx.accounts.faucet_state;
+    let faucet_claim = &mut ctx.accounts.faucet_claim;
+
+    require!(amount <= faucet_state.max_mint_amount, ErrorCode::FaucetCapExceeded);
+
+    let total_minted = faucet_state
+        .total_minted
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(total_minted <= faucet_state.total_cap, ErrorCode::FaucetCapExceeded);
+
+    if faucet_claim.wallet == Pubkey::default() {
+        faucet_claim.wallet = ctx.accounts.authority.key();
+        faucet_claim.bump = ctx.bumps.faucet_claim;
+    } else {
+        let next_mint_time = faucet_claim
+            .last_mint_time
+            .checked_add(faucet_state.cooldown)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(current_time >= next_mint_time, ErrorCode::FaucetCooldown);
+    }
+
     let global_state_bump = ctx.accounts.global_state.bump;
-    let seeds = &[GLOBAL_STATE_SEED, &[global_state_bump]];
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+    let seeds = &[GLOBAL_STATE_SEED, base_mint_key.as_ref(), &[global_state_bump]];
     let signer_seeds = &[&seeds[..]];
 
     let mint_accounts = MintTo {
@@ -45,7 +94,7 @@ pub fn handler(ctx: Context<MintTokens>, amount: u64) -> Result<()> {
         authority: ctx.accounts.global_state.to_account_info(),
     };
 
-    token_2022::mint_to(
+    token_interface::mint_to(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             mint_accounts,
@@ -54,6 +103,13 @@ pub fn handler(ctx: Context<MintTokens>, amount: u64) -> Result<()> {
         amount,
     )?;
 
+    faucet_state.total_minted = total_minted;
+    faucet_claim.last_mint_time = current_time;
+    faucet_claim.total_minted = faucet_claim
+        .total_minted
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
     msg!("Minted {} tokens to {}", amount, ctx.accounts.recipient_token_account.key());
 
     Ok(())
@@ -1,17 +1,67 @@
 pub mod initialize;
+pub mod initialize_with_mint;
 pub mod lock_tokens;
 pub mod increase_lock_amount;
 pub mod increase_lock_duration;
 pub mod withdraw;
+pub mod early_withdraw;
+pub mod split_lock;
+pub mod merge_locks;
 pub mod deposit_fees;
+pub mod notify_reward;
 pub mod claim_fees;
+pub mod claim_fees_for;
+pub mod claim_fees_batch;
+pub mod compound_fees;
+pub mod register_reward_mint;
+pub mod recover_undistributed_fees;
+pub mod checkpoint;
+pub mod get_voting_power;
+#[cfg(feature = "devnet-faucet")]
+pub mod configure_faucet;
+#[cfg(feature = "devnet-faucet")]
 pub mod mint_tokens;
+pub mod propose_authority;
+pub mod accept_authority;
+pub mod set_roles;
+pub mod set_fee_split;
+pub mod set_early_withdraw_penalty;
+pub mod set_claim_delegate;
+pub mod set_pause_state;
+pub mod queue_multiplier_update;
+pub mod apply_multiplier_update;
 
+// Every module exports a `handler`; lib.rs calls them by path
+#[allow(ambiguous_glob_reexports)]
 pub use initialize::*;
+pub use initialize_with_mint::*;
 pub use lock_tokens::*;
 pub use increase_lock_amount::*;
 pub use increase_lock_duration::*;
 pub use withdraw::*;
+pub use early_withdraw::*;
+pub use split_lock::*;
+pub use merge_locks::*;
 pub use deposit_fees::*;
+pub use notify_reward::*;
 pub use claim_fees::*;
+pub use claim_fees_for::*;
+pub use claim_fees_batch::*;
+pub use compound_fees::*;
+pub use register_reward_mint::*;
+pub use recover_undistributed_fees::*;
+pub use checkpoint::*;
+pub use get_voting_power::*;
+#[cfg(feature = "devnet-faucet")]
+pub use configure_faucet::*;
+#[cfg(feature = "devnet-faucet")]
 pub use mint_tokens::*;
+pub use propose_authority::*;
+pub use accept_authority::*;
+pub use set_roles::*;
+pub use set_fee_split::*;
+pub use set_early_withdraw_penalty::*;
+pub use set_claim_delegate::*;
+pub use set_pause_state::*;
+pub use queue_multiplier_update::*;
+pub use apply_multiplier_update::*;
@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::{advance_reward_stream, sync_fee_epoch, week_start};
+
+#[derive(Accounts)]
+pub struct NotifyReward<'info> {
+    #[account(
+        mut,
+        constraint = depositor.key() == global_state.fee_depositor
+    )]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), reward_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Account<'info, RewardToken>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = depositor,
+        associated_token::token_program = token_program
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.key() == reward_token.vault
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            reward_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Account<'info, FeeEpoch>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<NotifyReward>, amount: u64, duration: i64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.paused && !ctx.accounts.global_state.emergency_shutdown,
+        ErrorCode::ProtocolPaused
+    );
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(duration > 0, ErrorCode::InvalidRewardDuration);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let global_state_key = ctx.accounts.global_state.key();
+
+    // Settle the running stream before replacing its rate
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
+    let reward_token = &mut ctx.accounts.reward_token;
+
+    // What hasn't been credited yet rolls into the new rate
+    let leftover = if reward_token.last_update_time < reward_token.period_finish {
+        reward_token
+            .reward_rate
+            .checked_mul((reward_token.period_finish - reward_token.last_update_time) as u64)
+            .ok_or(ErrorCode::MathOverflow)?
+    } else {
+        0
+    };
+
+    let reward_rate = amount
+        .checked_add(leftover)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(duration as u64)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(reward_rate > 0, ErrorCode::InvalidRewardDuration);
+
+    reward_token.reward_rate = reward_rate;
+    reward_token.period_finish = current_time
+        .checked_add(duration)
+        .ok_or(ErrorCode::MathOverflow)?;
+    reward_token.last_update_time = current_time;
+    reward_token.total_deposited = reward_token
+        .total_deposited
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    advance_reward_stream(reward_token, &mut ctx.accounts.fee_epoch, current_time)?;
+
+    let transfer_accounts = TransferChecked {
+        from: ctx.accounts.depositor_token_account.to_account_info(),
+        mint: ctx.accounts.reward_mint.to_account_info(),
+        to: ctx.accounts.fee_vault.to_account_info(),
+        authority: ctx.accounts.depositor.to_account_info(),
+    };
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_accounts,
+        ),
+        amount,
+        ctx.accounts.reward_mint.decimals,
+    )?;
+
+    msg!("Streaming {} at {} per second until {}", amount, reward_rate, reward_token.period_finish);
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(
+        constraint = authority.key() == global_state.authority
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn handler(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+
+    // Proposing the default pubkey cancels a pending transfer
+    global_state.pending_authority = new_authority;
+
+    msg!("Proposed authority: {}", new_authority);
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::validate_lock_config;
+
+#[derive(Accounts)]
+pub struct QueueMultiplierUpdate<'info> {
+    #[account(
+        constraint = authority.key() == global_state.authority
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn handler(
+    ctx: Context<QueueMultiplierUpdate>,
+    lock_multiplier_numerator: u64,
+    lock_multiplier_denominator: u64,
+) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+
+    validate_lock_config(
+        lock_multiplier_numerator,
+        lock_multiplier_denominator,
+        global_state.max_lock_multiplier,
+        global_state.min_lock_duration,
+        global_state.max_lock_duration,
+    )?;
+
+    let effective_at = Clock::get()?
+        .unix_timestamp
+        .checked_add(LOCK_MULTIPLIER_UPDATE_DELAY)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Re-queueing replaces any pending update and restarts the delay
+    global_state.pending_lock_multiplier_numerator = lock_multiplier_numerator;
+    global_state.pending_lock_multiplier_denominator = lock_multiplier_denominator;
+    global_state.lock_multiplier_effective_at = effective_at;
+
+    msg!(
+        "Queued lock multiplier {}/{} effective at {}",
+        lock_multiplier_numerator,
+        lock_multiplier_denominator,
+        effective_at
+    );
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::pay_from_fee_vault;
+
+#[derive(Accounts)]
+pub struct RecoverUndistributedFees<'info> {
+    #[account(
+        constraint = authority.key() == global_state.authority
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), reward_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Account<'info, RewardToken>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = fee_vault.key() == reward_token.vault
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::token_program = token_program
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn handler(ctx: Context<RecoverUndistributedFees>) -> Result<()> {
+    let amount = ctx.accounts.reward_token.undistributed_fees;
+    require!(amount > 0, ErrorCode::NoFeesToClaim);
+
+    pay_from_fee_vault(
+        &ctx.accounts.global_state,
+        &ctx.accounts.fee_vault,
+        &ctx.accounts.reward_mint,
+        ctx.accounts.recipient_token_account.to_account_info(),
+        &ctx.accounts.token_program,
+        amount,
+    )?;
+
+    ctx.accounts.reward_token.undistributed_fees = 0;
+
+    msg!("Recovered {} undistributed {} fees", amount, ctx.accounts.reward_mint.key());
+
+    Ok(())
+}
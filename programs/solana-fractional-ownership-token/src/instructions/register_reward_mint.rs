@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::constants::*;
+use crate::state::*;
+use crate::utils::week_start;
+
+#[derive(Accounts)]
+pub struct RegisterRewardMint<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == global_state.authority
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<RewardToken>(),
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), reward_mint.key().as_ref()],
+        bump
+    )]
+    pub reward_token: Account<'info, RewardToken>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = reward_mint,
+        token::authority = global_state,
+        token::token_program = token_program,
+        seeds = [FEE_VAULT_SEED, global_state.base_mint.as_ref(), reward_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<RegisterRewardMint>) -> Result<()> {
+    write_reward_token(
+        &mut ctx.accounts.reward_token,
+        ctx.accounts.global_state.key(),
+        ctx.accounts.reward_mint.key(),
+        ctx.accounts.fee_vault.key(),
+        ctx.bumps.reward_token,
+    )?;
+
+    msg!("Registered reward mint {}", ctx.accounts.reward_mint.key());
+
+    Ok(())
+}
+
+pub(crate) fn write_reward_token(
+    reward_token: &mut RewardToken,
+    global_state: Pubkey,
+    mint: Pubkey,
+    vault: Pubkey,
+    bump: u8,
+) -> Result<()> {
+    reward_token.global_state = global_state;
+    reward_token.mint = mint;
+    reward_token.vault = vault;
+    reward_token.start_epoch = week_start(Clock::get()?.unix_timestamp);
+    reward_token.total_deposited = 0;
+    reward_token.total_treasury_fees = 0;
+    reward_token.total_burned = 0;
+    reward_token.total_penalties = 0;
+    reward_token.undistributed_fees = 0;
+    reward_token.deferred_fees = 0;
+    reward_token.deferred_epoch = 0;
+    reward_token.reward_rate = 0;
+    reward_token.period_finish = 0;
+    reward_token.last_update_time = 0;
+    reward_token.bump = bump;
+
+    Ok(())
+}
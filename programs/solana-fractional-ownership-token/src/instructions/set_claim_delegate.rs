@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct SetClaimDelegate<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            user_lock.user.as_ref(),
+            &user_lock.position_id.to_le_bytes()
+        ],
+        bump = user_lock.bump
+    )]
+    pub user_lock: Account<'info, UserLock>,
+
+    #[account(
+        token::mint = user_lock.position_mint,
+        token::authority = user,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn handler(ctx: Context<SetClaimDelegate>, delegate: Pubkey, fee_recipient: Pubkey) -> Result<()> {
+    require!(fee_recipient != Pubkey::default(), ErrorCode::InvalidFeeRecipient);
+
+    let user_lock = &mut ctx.accounts.user_lock;
+
+    user_lock.delegate = delegate;
+    user_lock.fee_recipient = fee_recipient;
+    user_lock.delegated_by = ctx.accounts.user.key();
+
+    msg!("Claim delegate: {}", delegate);
+    msg!("Fee recipient: {}", fee_recipient);
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct SetEarlyWithdrawPenalty<'info> {
+    #[account(
+        constraint = authority.key() == global_state.authority
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn handler(
+    ctx: Context<SetEarlyWithdrawPenalty>,
+    penalty_bps: u16,
+    penalty_cap_bps: u16,
+) -> Result<()> {
+    require!(
+        penalty_bps as u64 <= BPS_DENOMINATOR && penalty_cap_bps as u64 <= BPS_DENOMINATOR,
+        ErrorCode::InvalidPenaltyConfig
+    );
+
+    let global_state = &mut ctx.accounts.global_state;
+
+    global_state.early_withdraw_penalty_bps = penalty_bps;
+    global_state.early_withdraw_penalty_cap_bps = penalty_cap_bps;
+
+    msg!("Early withdraw penalty: {} bps at max duration, capped at {} bps", penalty_bps, penalty_cap_bps);
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct SetFeeSplit<'info> {
+    #[account(
+        constraint = authority.key() == global_state.authority
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn handler(
+    ctx: Context<SetFeeSplit>,
+    treasury: Pubkey,
+    treasury_fee_bps: u16,
+    burn_fee_bps: u16,
+) -> Result<()> {
+    require!(
+        treasury_fee_bps as u64 + burn_fee_bps as u64 <= BPS_DENOMINATOR,
+        ErrorCode::InvalidFeeSplit
+    );
+
+    let global_state = &mut ctx.accounts.global_state;
+
+    global_state.treasury = treasury;
+    global_state.treasury_fee_bps = treasury_fee_bps;
+    global_state.burn_fee_bps = burn_fee_bps;
+
+    msg!("Treasury: {}", treasury);
+    msg!("Fee split: {} bps treasury, {} bps burn", treasury_fee_bps, burn_fee_bps);
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct SetPauseState<'info> {
+    #[account(
+        constraint = pauser.key() == global_state.pauser
+    )]
+    pub pauser: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn handler(ctx: Context<SetPauseState>, paused: bool, emergency_shutdown: bool) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+
+    global_state.paused = paused;
+    global_state.emergency_shutdown = emergency_shutdown;
+
+    msg!("Paused: {}", paused);
+    msg!("Emergency shutdown: {}", emergency_shutdown);
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct SetRoles<'info> {
+    #[account(
+        constraint = authority.key() == global_state.authority
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn handler(ctx: Context<SetRoles>, fee_depositor: Pubkey, pauser: Pubkey) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+
+    global_state.fee_depositor = fee_depositor;
+    global_state.pauser = pauser;
+
+    msg!("Fee depositor: {}", fee_depositor);
+    msg!("Pauser: {}", pauser);
+
+    Ok(())
+}
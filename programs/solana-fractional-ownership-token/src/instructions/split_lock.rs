@@ -0,0 +1,309 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::Token2022,
+    token_interface::{self, Mint, TokenAccount, TokenInterface, MintTo, TransferChecked},
+};
+
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+use crate::utils::{
+    calculate_ve_point, record_lock_point, sync_fee_epoch, update_ve_supply, week_start,
+};
+
+#[derive(Accounts)]
+#[instruction(new_position_id: u64)]
+pub struct SplitLock<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            user_lock.user.as_ref(),
+            &user_lock.position_id.to_le_bytes()
+        ],
+        bump = user_lock.bump
+    )]
+    pub user_lock: Box<Account<'info, UserLock>>,
+
+    #[account(
+        token::mint = user_lock.position_mint,
+        token::authority = user,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LockPoint>(),
+        seeds = [LOCK_POINT_SEED, user_lock.key().as_ref(), &user_lock.point_count.to_le_bytes()],
+        bump
+    )]
+    pub lock_point: Box<Account<'info, LockPoint>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<UserLock>(),
+        seeds = [USER_LOCK_SEED, global_state.base_mint.as_ref(), user.key().as_ref(), &new_position_id.to_le_bytes()],
+        bump
+    )]
+    pub new_user_lock: Box<Account<'info, UserLock>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LockPoint>(),
+        seeds = [LOCK_POINT_SEED, new_user_lock.key().as_ref(), &new_user_lock.point_count.to_le_bytes()],
+        bump
+    )]
+    pub new_lock_point: Box<Account<'info, LockPoint>>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [POSITION_MINT_SEED, new_user_lock.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = global_state,
+        mint::token_program = position_token_program
+    )]
+    pub new_position_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = new_position_mint,
+        associated_token::authority = user,
+        associated_token::token_program = position_token_program
+    )]
+    pub new_position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), global_state.base_mint.as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Box<Account<'info, RewardToken>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            global_state.base_mint.as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Box<Account<'info, FeeEpoch>>,
+
+    #[account(
+        constraint = ve_mint.key() == global_state.ve_mint,
+        mint::token_program = ve_token_program
+    )]
+    pub ve_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [VE_ESCROW_SEED, user_lock.key().as_ref()],
+        bump
+    )]
+    pub ve_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = user,
+        token::mint = ve_mint,
+        token::authority = global_state,
+        token::token_program = ve_token_program,
+        seeds = [VE_ESCROW_SEED, new_user_lock.key().as_ref()],
+        bump
+    )]
+    pub new_ve_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub ve_token_program: Interface<'info, TokenInterface>,
+    pub position_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<SplitLock>, new_position_id: u64, amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.paused && !ctx.accounts.global_state.emergency_shutdown,
+        ErrorCode::ProtocolPaused
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let global_state_key = ctx.accounts.global_state.key();
+    let user_lock_key = ctx.accounts.user_lock.key();
+    let new_user_lock_key = ctx.accounts.new_user_lock.key();
+    let user_lock = &mut ctx.accounts.user_lock;
+
+    require!(user_lock.locked_amount > 0, ErrorCode::NoExistingLock);
+    require!(current_time < user_lock.unlock_time, ErrorCode::LockExpired);
+    require!(amount > 0 && amount < user_lock.locked_amount, ErrorCode::InvalidAmount);
+
+    // Both halves keep the original decay line, so ve is divided like the tokens
+    let split_ve_amount = (user_lock.initial_ve_amount as u128)
+        .checked_mul(amount as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(user_lock.locked_amount as u128)
+        .ok_or(ErrorCode::MathOverflow)? as u64;
+
+    record_lock_point(
+        &mut ctx.accounts.lock_point,
+        user_lock_key,
+        user_lock,
+        ctx.bumps.lock_point,
+        current_time,
+    )?;
+
+    let old_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+
+    user_lock.locked_amount = user_lock
+        .locked_amount
+        .checked_sub(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    user_lock.initial_ve_amount = user_lock
+        .initial_ve_amount
+        .checked_sub(split_ve_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let remaining_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+
+    let new_user_lock = &mut ctx.accounts.new_user_lock;
+    new_user_lock.user = ctx.accounts.user.key();
+    new_user_lock.position_id = new_position_id;
+    new_user_lock.position_mint = ctx.accounts.new_position_mint.key();
+    new_user_lock.delegate = Pubkey::default();
+    new_user_lock.fee_recipient = ctx.accounts.user.key();
+    new_user_lock.delegated_by = ctx.accounts.user.key();
+    new_user_lock.lock_multiplier_numerator = user_lock.lock_multiplier_numerator;
+    new_user_lock.lock_multiplier_denominator = user_lock.lock_multiplier_denominator;
+    new_user_lock.bump = ctx.bumps.new_user_lock;
+
+    // Archive the empty starting line so the new position earns only from the split onwards
+    record_lock_point(
+        &mut ctx.accounts.new_lock_point,
+        new_user_lock_key,
+        new_user_lock,
+        ctx.bumps.new_lock_point,
+        current_time,
+    )?;
+
+    new_user_lock.locked_amount = amount;
+    new_user_lock.unlock_time = user_lock.unlock_time;
+    new_user_lock.lock_start_time = user_lock.lock_start_time;
+    new_user_lock.initial_ve_amount = split_ve_amount;
+
+    let split_point = calculate_ve_point(
+        new_user_lock.initial_ve_amount,
+        new_user_lock.lock_start_time,
+        new_user_lock.unlock_time,
+        current_time,
+    )?;
+
+    let global_state_bump = ctx.accounts.global_state.bump;
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+    let seeds = &[GLOBAL_STATE_SEED, base_mint_key.as_ref(), &[global_state_bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let nft_accounts = MintTo {
+        mint: ctx.accounts.new_position_mint.to_account_info(),
+        to: ctx.accounts.new_position_token_account.to_account_info(),
+        authority: ctx.accounts.global_state.to_account_info(),
+    };
+
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.position_token_program.to_account_info(),
+            nft_accounts,
+            signer_seeds,
+        ),
+        1,
+    )?;
+
+    let ve_transfer_accounts = TransferChecked {
+        from: ctx.accounts.ve_escrow.to_account_info(),
+        mint: ctx.accounts.ve_mint.to_account_info(),
+        to: ctx.accounts.new_ve_escrow.to_account_info(),
+        authority: ctx.accounts.global_state.to_account_info(),
+    };
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.ve_token_program.to_account_info(),
+            ve_transfer_accounts,
+            signer_seeds,
+        ),
+        split_ve_amount,
+        ctx.accounts.ve_mint.decimals,
+    )?;
+
+    // Lock changes advance the base mint's stream before the supply moves
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
+    // Locked tokens stay in the vault and no ve is minted; only the decay lines are re-cut
+    let global_state = &mut ctx.accounts.global_state;
+    update_ve_supply(
+        global_state,
+        &mut ctx.accounts.slope_schedule,
+        (0, 0),
+        0,
+        split_point,
+        new_user_lock.unlock_time,
+    )?;
+    update_ve_supply(
+        global_state,
+        &mut ctx.accounts.slope_schedule,
+        old_point,
+        user_lock.unlock_time,
+        remaining_point,
+        user_lock.unlock_time,
+    )?;
+
+    msg!("Split {} tokens into position {}", amount, new_position_id);
+    msg!("Moved {} veTokens to the new position", split_ve_amount);
+
+    Ok(())
+}
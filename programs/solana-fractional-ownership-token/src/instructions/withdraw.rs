@@ -1,12 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token_2022::{self, Token2022, TransferChecked, Burn},
-    token_interface::{Mint, TokenAccount},
+    token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, Burn},
 };
 
 use crate::constants::*;
 use crate::error::ErrorCode;
 use crate::state::*;
+use crate::utils::{
+    accrue_epoch_rewards, calculate_ve_point, pay_from_fee_vault, record_lock_point, sync_fee_epoch,
+    update_ve_supply, week_start, write_user_reward,
+};
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
@@ -15,27 +18,73 @@ pub struct Withdraw<'info> {
 
     #[account(
         mut,
-        seeds = [USER_LOCK_SEED, user.key().as_ref()],
+        seeds = [
+            USER_LOCK_SEED,
+            global_state.base_mint.as_ref(),
+            user_lock.user.as_ref(),
+            &user_lock.position_id.to_le_bytes()
+        ],
         bump = user_lock.bump
     )]
     pub user_lock: Account<'info, UserLock>,
 
+    #[account(
+        token::mint = user_lock.position_mint,
+        token::authority = user,
+        constraint = position_token_account.amount == 1 @ ErrorCode::NotPositionHolder
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LockPoint>(),
+        seeds = [LOCK_POINT_SEED, user_lock.key().as_ref(), &user_lock.point_count.to_le_bytes()],
+        bump
+    )]
+    pub lock_point: Account<'info, LockPoint>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<UserReward>(),
+        seeds = [USER_REWARD_SEED, user_lock.key().as_ref(), base_mint.key().as_ref()],
+        bump
+    )]
+    pub user_reward: Box<Account<'info, UserReward>>,
+
     #[account(
         mut,
-        seeds = [GLOBAL_STATE_SEED],
+        seeds = [GLOBAL_STATE_SEED, global_state.base_mint.as_ref()],
         bump = global_state.bump
     )]
     pub global_state: Account<'info, GlobalState>,
 
     #[account(
         mut,
-        constraint = base_mint.key() == global_state.base_mint
+        seeds = [SLOPE_SCHEDULE_SEED, global_state.base_mint.as_ref()],
+        bump = slope_schedule.bump
+    )]
+    pub slope_schedule: Box<Account<'info, SlopeSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [REWARD_TOKEN_SEED, global_state.base_mint.as_ref(), base_mint.key().as_ref()],
+        bump = reward_token.bump
+    )]
+    pub reward_token: Box<Account<'info, RewardToken>>,
+
+    #[account(
+        mut,
+        constraint = base_mint.key() == global_state.base_mint,
+        mint::token_program = token_program
     )]
     pub base_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        constraint = ve_mint.key() == global_state.ve_mint
+        constraint = ve_mint.key() == global_state.ve_mint,
+        mint::token_program = ve_token_program
     )]
     pub ve_mint: InterfaceAccount<'info, Mint>,
 
@@ -49,51 +98,132 @@ pub struct Withdraw<'info> {
 
     #[account(
         mut,
-        associated_token::mint = ve_mint,
-        associated_token::authority = user,
-        associated_token::token_program = token_program
+        seeds = [VE_ESCROW_SEED, user_lock.key().as_ref()],
+        bump
     )]
-    pub user_ve_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub ve_escrow: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [TOKEN_VAULT_SEED],
+        seeds = [TOKEN_VAULT_SEED, global_state.base_mint.as_ref()],
         bump,
         constraint = token_vault.key() == global_state.token_vault
     )]
     pub token_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token2022>,
+    #[account(
+        mut,
+        constraint = fee_vault.key() == reward_token.vault
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<FeeEpoch>(),
+        seeds = [
+            FEE_EPOCH_SEED,
+            global_state.base_mint.as_ref(),
+            base_mint.key().as_ref(),
+            &week_start(Clock::get()?.unix_timestamp).to_le_bytes()
+        ],
+        bump
+    )]
+    pub fee_epoch: Box<Account<'info, FeeEpoch>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub ve_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<Withdraw>) -> Result<()> {
+/// With `claim_pending_fees`, base-mint fees are paid out as by `claim_fees`,
+/// from the lock points and closed epochs in `remaining_accounts`.
+pub fn handler(ctx: Context<Withdraw>, claim_pending_fees: bool) -> Result<()> {
     let current_time = Clock::get()?.unix_timestamp;
+    let user_lock_key = ctx.accounts.user_lock.key();
+    let global_state_key = ctx.accounts.global_state.key();
+
+    require!(ctx.accounts.user_lock.locked_amount > 0, ErrorCode::NoExistingLock);
+    // Emergency shutdown lets users recover principal before unlock_time
+    require!(
+        current_time >= ctx.accounts.user_lock.unlock_time || ctx.accounts.global_state.emergency_shutdown,
+        ErrorCode::LockNotExpired
+    );
+
+    sync_fee_epoch(
+        &mut ctx.accounts.global_state,
+        global_state_key,
+        &mut ctx.accounts.slope_schedule,
+        &mut ctx.accounts.reward_token,
+        &mut ctx.accounts.fee_epoch,
+        ctx.bumps.fee_epoch,
+        current_time,
+    )?;
+
+    // Settle against the decay line before withdrawing replaces it
+    let mut pending_fees = 0;
+    if claim_pending_fees {
+        let user_reward = &mut ctx.accounts.user_reward;
+        write_user_reward(
+            user_reward,
+            user_lock_key,
+            ctx.accounts.base_mint.key(),
+            ctx.accounts.reward_token.start_epoch,
+            ctx.bumps.user_reward,
+        );
+
+        pending_fees = accrue_epoch_rewards(
+            &ctx.accounts.user_lock,
+            &user_lock_key,
+            user_reward,
+            &ctx.accounts.global_state.base_mint,
+            ctx.remaining_accounts,
+            current_time,
+            ctx.program_id,
+        )?;
+    }
+
     let user_lock = &mut ctx.accounts.user_lock;
+    record_lock_point(
+        &mut ctx.accounts.lock_point,
+        user_lock_key,
+        user_lock,
+        ctx.bumps.lock_point,
+        current_time,
+    )?;
 
-    require!(user_lock.locked_amount > 0, ErrorCode::NoExistingLock);
-    require!(current_time >= user_lock.unlock_time, ErrorCode::LockNotExpired);
+    // Only non-zero when emergency shutdown releases a lock before expiry
+    let old_point = calculate_ve_point(
+        user_lock.initial_ve_amount,
+        user_lock.lock_start_time,
+        user_lock.unlock_time,
+        current_time,
+    )?;
+    let old_unlock_time = user_lock.unlock_time;
 
     let amount = user_lock.locked_amount;
     let ve_amount = user_lock.initial_ve_amount;
     let global_state_bump = ctx.accounts.global_state.bump;
+    let base_mint_key = ctx.accounts.global_state.base_mint;
+    let seeds = &[GLOBAL_STATE_SEED, base_mint_key.as_ref(), &[global_state_bump]];
+    let signer_seeds = &[&seeds[..]];
 
+    // The position's ve sits in its escrow, so any holder of the NFT can exit
     let burn_accounts = Burn {
         mint: ctx.accounts.ve_mint.to_account_info(),
-        from: ctx.accounts.user_ve_token_account.to_account_info(),
-        authority: ctx.accounts.user.to_account_info(),
+        from: ctx.accounts.ve_escrow.to_account_info(),
+        authority: ctx.accounts.global_state.to_account_info(),
     };
 
-    token_2022::burn(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
+    token_interface::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.ve_token_program.to_account_info(),
             burn_accounts,
+            signer_seeds,
         ),
         ve_amount,
     )?;
 
-    let seeds = &[GLOBAL_STATE_SEED, &[global_state_bump]];
-    let signer_seeds = &[&seeds[..]];
-
     let transfer_accounts = TransferChecked {
         from: ctx.accounts.token_vault.to_account_info(),
         mint: ctx.accounts.base_mint.to_account_info(),
@@ -101,7 +231,7 @@ pub fn handler(ctx: Context<Withdraw>) -> Result<()> {
         authority: ctx.accounts.global_state.to_account_info(),
     };
 
-    token_2022::transfer_checked(
+    token_interface::transfer_checked(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             transfer_accounts,
@@ -111,7 +241,29 @@ pub fn handler(ctx: Context<Withdraw>) -> Result<()> {
         ctx.accounts.base_mint.decimals,
     )?;
 
+    if pending_fees > 0 {
+        pay_from_fee_vault(
+            &ctx.accounts.global_state,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.base_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            pending_fees,
+        )?;
+
+        msg!("Claimed {} fees", pending_fees);
+    }
+
     let global_state = &mut ctx.accounts.global_state;
+    update_ve_supply(
+        global_state,
+        &mut ctx.accounts.slope_schedule,
+        old_point,
+        old_unlock_time,
+        (0, 0),
+        0,
+    )?;
+
     global_state.total_locked = global_state
         .total_locked
         .checked_sub(amount)
@@ -1,3 +1,7 @@
+// #[program] emits its IDL instructions beside the program module, outside any
+// item an attribute could reach, and they call the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 
 declare_id!("5xjnSTgkKABxfbBz5wtfWb2ye17piZo7ad5UBFuFybzQ");
@@ -18,12 +22,40 @@ pub mod solana_fractional_ownership_token {
         ctx: Context<Initialize>,
         lock_multiplier_numerator: u64,
         lock_multiplier_denominator: u64,
+        max_lock_multiplier: u64,
+        min_lock_duration: i64,
+        max_lock_duration: i64,
     ) -> Result<()> {
-        instructions::initialize::handler(ctx, lock_multiplier_numerator, lock_multiplier_denominator)
+        instructions::initialize::handler(
+            ctx,
+            lock_multiplier_numerator,
+            lock_multiplier_denominator,
+            max_lock_multiplier,
+            min_lock_duration,
+            max_lock_duration,
+        )
     }
 
-    pub fn lock_tokens(ctx: Context<LockTokens>, amount: u64, lock_duration: i64) -> Result<()> {
-        instructions::lock_tokens::handler(ctx, amount, lock_duration)
+    pub fn initialize_with_mint(
+        ctx: Context<InitializeWithMint>,
+        lock_multiplier_numerator: u64,
+        lock_multiplier_denominator: u64,
+        max_lock_multiplier: u64,
+        min_lock_duration: i64,
+        max_lock_duration: i64,
+    ) -> Result<()> {
+        instructions::initialize_with_mint::handler(
+            ctx,
+            lock_multiplier_numerator,
+            lock_multiplier_denominator,
+            max_lock_multiplier,
+            min_lock_duration,
+            max_lock_duration,
+        )
+    }
+
+    pub fn lock_tokens(ctx: Context<LockTokens>, position_id: u64, amount: u64, lock_duration: i64) -> Result<()> {
+        instructions::lock_tokens::handler(ctx, position_id, amount, lock_duration)
     }
 
     pub fn increase_lock_amount(ctx: Context<IncreaseLockAmount>, additional_amount: u64) -> Result<()> {
@@ -34,19 +66,134 @@ pub mod solana_fractional_ownership_token {
         instructions::increase_lock_duration::handler(ctx, additional_duration)
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
-        instructions::withdraw::handler(ctx)
+    pub fn withdraw(ctx: Context<Withdraw>, claim_pending_fees: bool) -> Result<()> {
+        instructions::withdraw::handler(ctx, claim_pending_fees)
+    }
+
+    pub fn early_withdraw(ctx: Context<EarlyWithdraw>) -> Result<()> {
+        instructions::early_withdraw::handler(ctx)
+    }
+
+    pub fn split_lock(ctx: Context<SplitLock>, new_position_id: u64, amount: u64) -> Result<()> {
+        instructions::split_lock::handler(ctx, new_position_id, amount)
     }
 
+    pub fn merge_locks(ctx: Context<MergeLocks>, source_account_count: u8) -> Result<()> {
+        instructions::merge_locks::handler(ctx, source_account_count)
+    }
+
+    /// Permissionless: any signer can deposit from a token account it owns. Other programs
+    /// can call it with `cpi::deposit_fees` (`cpi` feature), signing as their own PDA.
     pub fn deposit_fees(ctx: Context<DepositFees>, amount: u64) -> Result<()> {
         instructions::deposit_fees::handler(ctx, amount)
     }
 
+    pub fn notify_reward(ctx: Context<NotifyReward>, amount: u64, duration: i64) -> Result<()> {
+        instructions::notify_reward::handler(ctx, amount, duration)
+    }
+
     pub fn claim_fees(ctx: Context<ClaimFees>) -> Result<()> {
         instructions::claim_fees::handler(ctx)
     }
 
+    pub fn claim_fees_for(ctx: Context<ClaimFeesFor>) -> Result<()> {
+        instructions::claim_fees_for::handler(ctx)
+    }
+
+    pub fn claim_fees_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimFeesBatch<'info>>,
+        first_epoch: i64,
+        epoch_count: u8,
+        point_counts: Vec<u8>,
+    ) -> Result<()> {
+        instructions::claim_fees_batch::handler(ctx, first_epoch, epoch_count, point_counts)
+    }
+
+    pub fn compound_fees(ctx: Context<CompoundFees>) -> Result<()> {
+        instructions::compound_fees::handler(ctx)
+    }
+
+    pub fn register_reward_mint(ctx: Context<RegisterRewardMint>) -> Result<()> {
+        instructions::register_reward_mint::handler(ctx)
+    }
+
+    pub fn recover_undistributed_fees(ctx: Context<RecoverUndistributedFees>) -> Result<()> {
+        instructions::recover_undistributed_fees::handler(ctx)
+    }
+
+    pub fn checkpoint(ctx: Context<Checkpoint>) -> Result<()> {
+        instructions::checkpoint::handler(ctx)
+    }
+
+    pub fn get_voting_power<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetVotingPower<'info>>,
+        holder: Pubkey,
+    ) -> Result<u64> {
+        instructions::get_voting_power::handler(ctx, holder)
+    }
+
+    #[cfg(feature = "devnet-faucet")]
+    pub fn configure_faucet(
+        ctx: Context<ConfigureFaucet>,
+        max_mint_amount: u64,
+        cooldown: i64,
+        total_cap: u64,
+        allowlist: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::configure_faucet::handler(ctx, max_mint_amount, cooldown, total_cap, allowlist)
+    }
+
+    #[cfg(feature = "devnet-faucet")]
     pub fn mint_tokens(ctx: Context<MintTokens>, amount: u64) -> Result<()> {
         instructions::mint_tokens::handler(ctx, amount)
     }
+
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        instructions::propose_authority::handler(ctx, new_authority)
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        instructions::accept_authority::handler(ctx)
+    }
+
+    pub fn set_roles(ctx: Context<SetRoles>, fee_depositor: Pubkey, pauser: Pubkey) -> Result<()> {
+        instructions::set_roles::handler(ctx, fee_depositor, pauser)
+    }
+
+    pub fn set_fee_split(
+        ctx: Context<SetFeeSplit>,
+        treasury: Pubkey,
+        treasury_fee_bps: u16,
+        burn_fee_bps: u16,
+    ) -> Result<()> {
+        instructions::set_fee_split::handler(ctx, treasury, treasury_fee_bps, burn_fee_bps)
+    }
+
+    pub fn set_early_withdraw_penalty(
+        ctx: Context<SetEarlyWithdrawPenalty>,
+        penalty_bps: u16,
+        penalty_cap_bps: u16,
+    ) -> Result<()> {
+        instructions::set_early_withdraw_penalty::handler(ctx, penalty_bps, penalty_cap_bps)
+    }
+
+    pub fn set_claim_delegate(ctx: Context<SetClaimDelegate>, delegate: Pubkey, fee_recipient: Pubkey) -> Result<()> {
+        instructions::set_claim_delegate::handler(ctx, delegate, fee_recipient)
+    }
+
+    pub fn set_pause_state(ctx: Context<SetPauseState>, paused: bool, emergency_shutdown: bool) -> Result<()> {
+        instructions::set_pause_state::handler(ctx, paused, emergency_shutdown)
+    }
+
+    pub fn queue_multiplier_update(
+        ctx: Context<QueueMultiplierUpdate>,
+        lock_multiplier_numerator: u64,
+        lock_multiplier_denominator: u64,
+    ) -> Result<()> {
+        instructions::queue_multiplier_update::handler(ctx, lock_multiplier_numerator, lock_multiplier_denominator)
+    }
+
+    pub fn apply_multiplier_update(ctx: Context<ApplyMultiplierUpdate>) -> Result<()> {
+        instructions::apply_multiplier_update::handler(ctx)
+    }
 }
@@ -3,27 +3,141 @@ use anchor_lang::prelude::*;
 #[account]
 pub struct GlobalState {
     pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub fee_depositor: Pubkey,
+    pub pauser: Pubkey,
+    pub treasury: Pubkey, // Owner of the token accounts that receive the treasury share
     pub base_mint: Pubkey,
     pub ve_mint: Pubkey,
     pub token_vault: Pubkey,
     pub fee_vault: Pubkey,
     pub total_locked: u64,
-    pub total_ve_supply: u64,
-    pub total_fees_deposited: u64,
-    pub cumulative_fee_per_ve_token: u128, // Scaled by 1e18 for precision
+    pub total_ve_supply: u64, // Undecayed sum of initial_ve_amount
+    pub ve_bias: u128,        // Decayed ve supply at ve_checkpoint_time, scaled by VE_SLOPE_PRECISION
+    pub ve_slope: u128,
+    pub ve_checkpoint_time: i64,
+    pub ve_epoch_start: i64,  // Latest week boundary the supply was checkpointed across
+    pub ve_epoch_supply: u64, // Decayed ve supply at ve_epoch_start
     pub lock_multiplier_numerator: u64,
     pub lock_multiplier_denominator: u64,
+    pub pending_lock_multiplier_numerator: u64,
+    pub pending_lock_multiplier_denominator: u64,
+    pub lock_multiplier_effective_at: i64, // 0 when no update is queued
+    pub min_lock_duration: i64,
+    pub max_lock_duration: i64,
+    pub max_lock_multiplier: u64, // Cap on numerator / denominator, fixed at initialize
+    pub treasury_fee_bps: u16,
+    pub burn_fee_bps: u16,
+    pub early_withdraw_penalty_bps: u16, // Penalty with max_lock_duration left, falling linearly to zero at unlock
+    pub early_withdraw_penalty_cap_bps: u16,
+    pub paused: bool,
+    pub emergency_shutdown: bool,
+    pub bump: u8,
+}
+
+/// Global slope decreases keyed by week, stored as a ring buffer indexed by
+/// `(week_start / WEEK) % SLOPE_SCHEDULE_WEEKS`.
+#[account]
+pub struct SlopeSchedule {
+    pub global_state: Pubkey,
+    pub slope_changes: [u128; crate::constants::SLOPE_SCHEDULE_WEEKS],
+    pub bump: u8,
+}
+
+/// Registry entry for a mint that fees can be paid in. The base mint is
+/// registered at initialize; others are added by the authority.
+#[account]
+pub struct RewardToken {
+    pub global_state: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub start_epoch: i64, // First fee epoch the mint can be deposited into
+    pub total_deposited: u64,
+    pub total_treasury_fees: u64,
+    pub total_burned: u64,
+    pub total_penalties: u64, // Early withdraw penalties paid in; base mint only
+    pub undistributed_fees: u64, // Deposited while ve supply was zero; rolls into the next epoch
+    pub deferred_fees: u64, // Penalties paid in during deferred_epoch; credited to a later epoch
+    pub deferred_epoch: i64,
+    pub reward_rate: u64, // Tokens streamed per second until period_finish
+    pub period_finish: i64,
+    pub last_update_time: i64, // Stream credited to fee epochs up to here
+    pub bump: u8,
+}
+
+/// Fees of one reward mint deposited during one week, paid out pro rata to
+/// ve balances at the week's start once the week has closed.
+#[account]
+pub struct FeeEpoch {
+    pub global_state: Pubkey,
+    pub reward_mint: Pubkey,
+    pub epoch_start: i64,
+    pub total_fees: u64,
+    pub ve_supply: u64, // Decayed global ve supply at epoch_start
+    pub claimed_fees: u64,
+    pub bump: u8,
+}
+
+/// A lock's decay line before it was modified, kept so fee epochs can be
+/// priced off the balance the lock actually had at each epoch boundary.
+#[account]
+pub struct LockPoint {
+    pub user_lock: Pubkey,
+    pub index: u64,
+    pub initial_ve_amount: u64,
+    pub lock_start_time: i64,
+    pub unlock_time: i64,
+    pub set_time: i64, // When this line took effect
+    pub bump: u8,
+}
+
+/// Per-lock, per-reward-mint claim progress.
+#[account]
+pub struct UserReward {
+    pub user_lock: Pubkey,
+    pub reward_mint: Pubkey,
+    pub epoch_cursor: i64, // Next fee epoch to claim
+    pub point_index: u64,  // Lock point in effect at epoch_cursor
+    pub fees_claimed: u64,
+    pub bump: u8,
+}
+
+#[cfg(feature = "devnet-faucet")]
+#[account]
+pub struct FaucetState {
+    pub global_state: Pubkey,
+    pub max_mint_amount: u64,
+    pub cooldown: i64,
+    pub total_cap: u64,
+    pub total_minted: u64,
+    pub allowlist: [Pubkey; crate::constants::MAX_FAUCET_ALLOWLIST],
+    pub bump: u8,
+}
+
+#[cfg(feature = "devnet-faucet")]
+#[account]
+pub struct FaucetClaim {
+    pub wallet: Pubkey, // Signer the cooldown applies to, whichever account receives the mint
+    pub last_mint_time: i64,
+    pub total_minted: u64,
     pub bump: u8,
 }
 
 #[account]
 pub struct UserLock {
-    pub user: Pubkey,
+    pub user: Pubkey,          // Wallet that opened the position; control follows the position NFT
+    pub position_id: u64,
+    pub position_mint: Pubkey, // Token-2022 NFT whose holder controls the position
+    pub delegate: Pubkey,      // May claim for the holder; default when unset
+    pub fee_recipient: Pubkey, // Owner of the token accounts claim_fees_for pays into
+    pub delegated_by: Pubkey,  // Holder that set delegate and fee_recipient; stale once the NFT moves
     pub locked_amount: u64,
     pub unlock_time: i64,
     pub lock_start_time: i64,
     pub initial_ve_amount: u64,
-    pub fees_claimed: u64,
-    pub fee_debt: u128,
+    pub point_count: u64,     // Archived LockPoints; the live line is point `point_count`
+    pub last_point_time: i64, // When the live line took effect
+    pub lock_multiplier_numerator: u64, // Multiplier the position was minted under
+    pub lock_multiplier_denominator: u64,
     pub bump: u8,
 }
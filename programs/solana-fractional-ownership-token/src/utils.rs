@@ -1,22 +1,50 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 use crate::constants::*;
 use crate::error::ErrorCode;
+use crate::state::{FeeEpoch, GlobalState, LockPoint, RewardToken, SlopeSchedule, UserLock, UserReward};
+
+pub fn validate_lock_config(
+    numerator: u64,
+    denominator: u64,
+    max_lock_multiplier: u64,
+    min_lock_duration: i64,
+    max_lock_duration: i64,
+) -> Result<()> {
+    require!(
+        denominator > 0
+            && max_lock_multiplier > 0
+            && numerator >= denominator
+            && numerator <= denominator.saturating_mul(max_lock_multiplier),
+        ErrorCode::InvalidLockMultiplier
+    );
+    require!(
+        min_lock_duration > 0
+            && min_lock_duration < max_lock_duration
+            && max_lock_duration <= MAX_SUPPORTED_LOCK_DURATION,
+        ErrorCode::InvalidLockDurationBounds
+    );
+
+    Ok(())
+}
 
 pub fn calculate_time_multiplier(
     lock_duration: i64,
+    min_lock_duration: i64,
+    max_lock_duration: i64,
     numerator: u64,
     denominator: u64,
 ) -> Result<u64> {
-    if lock_duration >= MAX_LOCK_DURATION {
+    if lock_duration >= max_lock_duration {
         return Ok(numerator);
     }
 
-    if lock_duration <= MIN_LOCK_DURATION {
+    if lock_duration <= min_lock_duration {
         return Ok(denominator);
     }
 
-    let duration_range = MAX_LOCK_DURATION - MIN_LOCK_DURATION;
-    let duration_offset = lock_duration - MIN_LOCK_DURATION;
+    let duration_range = max_lock_duration - min_lock_duration;
+    let duration_offset = lock_duration - min_lock_duration;
     let multiplier_range = numerator - denominator;
 
     let multiplier = denominator
@@ -62,3 +90,932 @@ pub fn calculate_current_ve_balance(
 
     Ok(current_balance)
 }
+
+/// Start of the weekly fee epoch containing `timestamp`.
+pub fn week_start(timestamp: i64) -> i64 {
+    timestamp / WEEK * WEEK
+}
+
+/// `bps` basis points of `amount`, rounded down.
+pub fn bps_share(amount: u64, bps: u16) -> Result<u64> {
+    let share = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+
+    u64::try_from(share).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Fills in a fee epoch the first time it is used, pricing it off the ve
+/// supply at the epoch start. The supply must already be checkpointed.
+pub fn open_fee_epoch(
+    fee_epoch: &mut FeeEpoch,
+    global_state: &GlobalState,
+    global_state_key: Pubkey,
+    reward_mint: Pubkey,
+    bump: u8,
+) {
+    if fee_epoch.global_state != Pubkey::default() {
+        return;
+    }
+
+    fee_epoch.global_state = global_state_key;
+    fee_epoch.reward_mint = reward_mint;
+    fee_epoch.epoch_start = global_state.ve_epoch_start;
+    fee_epoch.total_fees = 0;
+    fee_epoch.ve_supply = global_state.ve_epoch_supply;
+    fee_epoch.claimed_fees = 0;
+    fee_epoch.bump = bump;
+}
+
+/// Share of `amount` forfeited when leaving a lock with `remaining_duration` to go.
+pub fn early_withdraw_penalty(
+    amount: u64,
+    remaining_duration: i64,
+    global_state: &GlobalState,
+) -> Result<u64> {
+    let remaining_duration = remaining_duration.clamp(0, global_state.max_lock_duration);
+    let penalty_bps = (global_state.early_withdraw_penalty_bps as u128)
+        .checked_mul(remaining_duration as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(global_state.max_lock_duration as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .min(global_state.early_withdraw_penalty_cap_bps as u128);
+
+    bps_share(amount, penalty_bps as u16)
+}
+
+/// Adds fees to an epoch, together with anything held back from earlier
+/// epochs. Epochs that opened with no ve supply can't pay anyone, so their
+/// fees are held in `undistributed_fees` instead.
+pub fn credit_fee_epoch(
+    reward_token: &mut RewardToken,
+    fee_epoch: &mut FeeEpoch,
+    amount: u64,
+) -> Result<()> {
+    if fee_epoch.ve_supply == 0 {
+        reward_token.undistributed_fees = reward_token
+            .undistributed_fees
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        return Ok(());
+    }
+
+    fee_epoch.total_fees = fee_epoch
+        .total_fees
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_add(reward_token.undistributed_fees)
+        .ok_or(ErrorCode::MathOverflow)?;
+    reward_token.undistributed_fees = 0;
+
+    Ok(())
+}
+
+/// Holds fees back until the epoch after `current_epoch`, for fees whose
+/// payer is still priced into the open epoch.
+pub fn defer_fees(reward_token: &mut RewardToken, amount: u64, current_epoch: i64) -> Result<()> {
+    reward_token.deferred_fees = reward_token
+        .deferred_fees
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    reward_token.deferred_epoch = current_epoch;
+
+    Ok(())
+}
+
+/// Credits fees deferred in an earlier week into the open epoch.
+pub fn release_deferred_fees(reward_token: &mut RewardToken, fee_epoch: &mut FeeEpoch) -> Result<()> {
+    if reward_token.deferred_fees == 0 || fee_epoch.epoch_start <= reward_token.deferred_epoch {
+        return Ok(());
+    }
+
+    let amount = reward_token.deferred_fees;
+    reward_token.deferred_fees = 0;
+    credit_fee_epoch(reward_token, fee_epoch, amount)
+}
+
+/// Credits the stream into the current fee epoch up to the end of its week,
+/// stopping at `period_finish`. The epoch only pays out once the week has
+/// closed, so its whole share can be credited at the first touch. Weeks in
+/// which nobody touched the mint had no epoch of their own, so what the
+/// stream emitted in them is credited here and emission stays linear.
+pub fn advance_reward_stream(
+    reward_token: &mut RewardToken,
+    fee_epoch: &mut FeeEpoch,
+    current_time: i64,
+) -> Result<u64> {
+    let stream_end = reward_token
+        .period_finish
+        .min(week_start(current_time) + WEEK)
+        .max(reward_token.last_update_time);
+    let streamed = reward_token
+        .reward_rate
+        .checked_mul((stream_end - reward_token.last_update_time) as u64)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    credit_fee_epoch(reward_token, fee_epoch, streamed)?;
+    reward_token.last_update_time = stream_end;
+
+    Ok(streamed)
+}
+
+/// Checkpoints the ve supply, opens the current fee epoch of the reward
+/// token's mint and credits deferred fees and its stream. Run before fees of
+/// a mint are deposited, claimed or penalties are paid in.
+pub fn sync_fee_epoch(
+    global_state: &mut GlobalState,
+    global_state_key: Pubkey,
+    slope_schedule: &mut SlopeSchedule,
+    reward_token: &mut RewardToken,
+    fee_epoch: &mut FeeEpoch,
+    fee_epoch_bump: u8,
+    current_time: i64,
+) -> Result<()> {
+    checkpoint_ve_supply(global_state, slope_schedule, current_time)?;
+    open_fee_epoch(fee_epoch, global_state, global_state_key, reward_token.mint, fee_epoch_bump);
+    release_deferred_fees(reward_token, fee_epoch)?;
+    advance_reward_stream(reward_token, fee_epoch, current_time)?;
+
+    Ok(())
+}
+
+/// Fills in a claim cursor the first time a lock claims a mint.
+pub fn write_user_reward(
+    user_reward: &mut UserReward,
+    user_lock: Pubkey,
+    reward_mint: Pubkey,
+    start_epoch: i64,
+    bump: u8,
+) {
+    if user_reward.user_lock != Pubkey::default() {
+        return;
+    }
+
+    user_reward.user_lock = user_lock;
+    user_reward.reward_mint = reward_mint;
+    user_reward.epoch_cursor = start_epoch;
+    user_reward.point_index = 0;
+    user_reward.fees_claimed = 0;
+    user_reward.bump = bump;
+}
+
+/// Pays accrued fees out of a mint's fee vault, signed by the global state.
+pub fn pay_from_fee_vault<'info>(
+    global_state: &Account<'info, GlobalState>,
+    fee_vault: &InterfaceAccount<'info, TokenAccount>,
+    reward_mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[GLOBAL_STATE_SEED, global_state.base_mint.as_ref(), &[global_state.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer_accounts = TransferChecked {
+        from: fee_vault.to_account_info(),
+        mint: reward_mint.to_account_info(),
+        to,
+        authority: global_state.to_account_info(),
+    };
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        ),
+        amount,
+        reward_mint.decimals,
+    )
+}
+
+/// Archives the lock's live decay line into `lock_point` before it is
+/// modified. Must run before any change to a position's ve weight.
+pub fn record_lock_point(
+    lock_point: &mut LockPoint,
+    user_lock_key: Pubkey,
+    user_lock: &mut UserLock,
+    bump: u8,
+    current_time: i64,
+) -> Result<()> {
+    lock_point.user_lock = user_lock_key;
+    lock_point.index = user_lock.point_count;
+    lock_point.initial_ve_amount = user_lock.initial_ve_amount;
+    lock_point.lock_start_time = user_lock.lock_start_time;
+    lock_point.unlock_time = user_lock.unlock_time;
+    lock_point.set_time = user_lock.last_point_time;
+    lock_point.bump = bump;
+
+    user_lock.point_count = user_lock
+        .point_count
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    user_lock.last_point_time = current_time;
+
+    Ok(())
+}
+
+/// Walks closed fee epochs of one reward mint from the claim cursor and
+/// returns the position's share. `epoch_accounts` holds the archived
+/// `LockPoint`s from `user_reward.point_index` onwards, followed by the
+/// `FeeEpoch` accounts starting at `user_reward.epoch_cursor`. Weeks without
+/// deposits have no account and pay nothing. Stops early when accounts run
+/// out, so claims can resume in a later transaction. Epochs are only written
+/// once the whole walk succeeds, so an error leaves every account untouched.
+pub fn accrue_epoch_rewards(
+    user_lock: &UserLock,
+    user_lock_key: &Pubkey,
+    user_reward: &mut UserReward,
+    base_mint: &Pubkey,
+    epoch_accounts: &[AccountInfo],
+    current_time: i64,
+    program_id: &Pubkey,
+) -> Result<u64> {
+    accrue_rewards(
+        user_lock,
+        user_lock_key,
+        user_reward,
+        base_mint,
+        epoch_accounts,
+        None,
+        current_time,
+        program_id,
+    )
+}
+
+/// Same as `accrue_epoch_rewards`, for a run of `FeeEpoch` accounts shared by
+/// many locks: `epoch_run` holds one account per week from `first_epoch`, and
+/// `lock_points` the lock's own archived points. Weeks the cursor jumps over
+/// are skipped in the run as well. An epoch before `first_epoch` or past the
+/// end of the run stops the walk.
+#[allow(clippy::too_many_arguments)]
+pub fn accrue_shared_epoch_rewards<'info>(
+    user_lock: &UserLock,
+    user_lock_key: &Pubkey,
+    user_reward: &mut UserReward,
+    base_mint: &Pubkey,
+    lock_points: &[AccountInfo<'info>],
+    epoch_run: &[AccountInfo<'info>],
+    first_epoch: i64,
+    current_time: i64,
+    program_id: &Pubkey,
+) -> Result<u64> {
+    accrue_rewards(
+        user_lock,
+        user_lock_key,
+        user_reward,
+        base_mint,
+        lock_points,
+        Some((epoch_run, first_epoch)),
+        current_time,
+        program_id,
+    )
+}
+
+/// `epoch_accounts` holds the lock points, followed by the fee epochs unless
+/// a shared run is given.
+#[allow(clippy::too_many_arguments)]
+fn accrue_rewards<'info>(
+    user_lock: &UserLock,
+    user_lock_key: &Pubkey,
+    user_reward: &mut UserReward,
+    base_mint: &Pubkey,
+    epoch_accounts: &[AccountInfo<'info>],
+    shared_run: Option<(&[AccountInfo<'info>], i64)>,
+    current_time: i64,
+    program_id: &Pubkey,
+) -> Result<u64> {
+    let current_epoch = week_start(current_time);
+    let mut next_account = 0;
+
+    // Decay lines in effect from the cursor onwards, oldest first
+    let mut lines: Vec<LockPoint> = Vec::new();
+    let mut next_index = user_reward.point_index;
+    while next_index < user_lock.point_count {
+        let Some(info) = epoch_accounts.get(next_account) else {
+            break;
+        };
+        if info.owner != program_id {
+            break;
+        }
+        let Ok(point) = LockPoint::try_deserialize(&mut &info.try_borrow_data()?[..]) else {
+            break;
+        };
+        require_keys_eq!(point.user_lock, *user_lock_key, ErrorCode::InvalidLockPoint);
+        require!(point.index == next_index, ErrorCode::InvalidLockPoint);
+
+        lines.push(point);
+        next_account += 1;
+        next_index += 1;
+    }
+    let fee_epoch_accounts = match shared_run {
+        Some((epoch_run, _)) => epoch_run,
+        None => &epoch_accounts[next_account..],
+    };
+    let mut next_fee_epoch = 0;
+
+    let has_live_line = next_index == user_lock.point_count;
+    if has_live_line {
+        lines.push(LockPoint {
+            user_lock: *user_lock_key,
+            index: user_lock.point_count,
+            initial_ve_amount: user_lock.initial_ve_amount,
+            lock_start_time: user_lock.lock_start_time,
+            unlock_time: user_lock.unlock_time,
+            set_time: user_lock.last_point_time,
+            bump: 0,
+        });
+    }
+
+    let mut line_index = 0;
+    let mut accrued: u64 = 0;
+    let mut claimed_epochs: Vec<(&AccountInfo, FeeEpoch)> = Vec::new();
+
+    while !lines.is_empty() && user_reward.epoch_cursor < current_epoch {
+        let epoch = user_reward.epoch_cursor;
+
+        // A line set exactly at the boundary is not in the recorded supply
+        while line_index + 1 < lines.len() && lines[line_index + 1].set_time < epoch {
+            line_index += 1;
+        }
+        if line_index + 1 == lines.len() && !has_live_line {
+            break;
+        }
+
+        let line = &lines[line_index];
+        if line.unlock_time <= epoch {
+            // Nothing to earn until the next line takes effect
+            user_reward.epoch_cursor = match lines.get(line_index + 1) {
+                Some(next_line) => (next_line.set_time / WEEK + 1) * WEEK,
+                None => current_epoch,
+            }
+            .min(current_epoch);
+            continue;
+        }
+
+        let epoch_info = match shared_run {
+            Some((_, first_epoch)) if epoch < first_epoch => None,
+            Some((_, first_epoch)) => fee_epoch_accounts.get(((epoch - first_epoch) / WEEK) as usize),
+            None => {
+                next_fee_epoch += 1;
+                fee_epoch_accounts.get(next_fee_epoch - 1)
+            }
+        };
+        let Some(epoch_info) = epoch_info else {
+            break;
+        };
+
+        let (expected_key, _) = Pubkey::find_program_address(
+            &[
+                FEE_EPOCH_SEED,
+                base_mint.as_ref(),
+                user_reward.reward_mint.as_ref(),
+                &epoch.to_le_bytes(),
+            ],
+            program_id,
+        );
+        require_keys_eq!(epoch_info.key(), expected_key, ErrorCode::InvalidFeeEpoch);
+
+        if epoch_info.owner == program_id && !epoch_info.data_is_empty() {
+            let mut fee_epoch = FeeEpoch::try_deserialize(&mut &epoch_info.try_borrow_data()?[..])?;
+            let ve_balance = calculate_current_ve_balance(
+                line.initial_ve_amount,
+                line.lock_start_time,
+                line.unlock_time,
+                epoch,
+            )?;
+
+            if ve_balance > 0 && fee_epoch.ve_supply > 0 {
+                let share = (fee_epoch.total_fees as u128)
+                    .checked_mul(ve_balance as u128)
+                    .ok_or(ErrorCode::MathOverflow)?
+                    .checked_div(fee_epoch.ve_supply as u128)
+                    .ok_or(ErrorCode::MathOverflow)? as u64;
+                // Rounding between per-user and global decay must never overdraw an epoch
+                let share = share.min(fee_epoch.total_fees.saturating_sub(fee_epoch.claimed_fees));
+
+                fee_epoch.claimed_fees = fee_epoch
+                    .claimed_fees
+                    .checked_add(share)
+                    .ok_or(ErrorCode::MathOverflow)?;
+                claimed_epochs.push((epoch_info, fee_epoch));
+
+                accrued = accrued.checked_add(share).ok_or(ErrorCode::MathOverflow)?;
+            }
+        }
+
+        user_reward.epoch_cursor = epoch + WEEK;
+    }
+
+    for (epoch_info, fee_epoch) in claimed_epochs {
+        fee_epoch.try_serialize(&mut &mut epoch_info.try_borrow_mut_data()?[..])?;
+    }
+
+    user_reward.point_index = user_reward
+        .point_index
+        .checked_add(line_index as u64)
+        .ok_or(ErrorCode::MathOverflow)?;
+    user_reward.fees_claimed = user_reward
+        .fees_claimed
+        .checked_add(accrued)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(accrued)
+}
+
+/// Unlock times are rounded up to a week boundary so slope changes can be
+/// scheduled per week.
+pub fn round_up_to_week(timestamp: i64) -> Result<i64> {
+    let rounded = timestamp
+        .checked_add(WEEK - 1)
+        .ok_or(ErrorCode::MathOverflow)?
+        / WEEK
+        * WEEK;
+
+    Ok(rounded)
+}
+
+fn slope_schedule_index(week_start: i64) -> usize {
+    ((week_start / WEEK) as usize) % SLOPE_SCHEDULE_WEEKS
+}
+
+/// Remaining bias and slope of a lock's decay line at `current_time`, both
+/// scaled by `VE_SLOPE_PRECISION`. Expired locks contribute nothing.
+pub fn calculate_ve_point(
+    initial_ve_amount: u64,
+    lock_start_time: i64,
+    unlock_time: i64,
+    current_time: i64,
+) -> Result<(u128, u128)> {
+    if initial_ve_amount == 0 || unlock_time <= current_time {
+        return Ok((0, 0));
+    }
+
+    let total_duration = unlock_time
+        .checked_sub(lock_start_time)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let slope = (initial_ve_amount as u128)
+        .checked_mul(VE_SLOPE_PRECISION)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(total_duration as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let bias = slope
+        .checked_mul((unlock_time - current_time) as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok((bias, slope))
+}
+
+/// Decays the global bias up to `current_time`, applying scheduled slope
+/// changes and recording the supply at every week boundary crossed.
+pub fn checkpoint_ve_supply(
+    global_state: &mut GlobalState,
+    slope_schedule: &mut SlopeSchedule,
+    current_time: i64,
+) -> Result<()> {
+    let mut checkpoint_time = global_state.ve_checkpoint_time;
+
+    while checkpoint_time < current_time {
+        if global_state.ve_bias == 0 && global_state.ve_slope == 0 {
+            checkpoint_time = current_time;
+            global_state.ve_epoch_start = week_start(current_time);
+            global_state.ve_epoch_supply = 0;
+            break;
+        }
+
+        let next_week = (checkpoint_time / WEEK + 1) * WEEK;
+        let step_end = next_week.min(current_time);
+
+        let decay = global_state
+            .ve_slope
+            .checked_mul((step_end - checkpoint_time) as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        global_state.ve_bias = global_state.ve_bias.saturating_sub(decay);
+
+        if step_end == next_week {
+            let index = slope_schedule_index(next_week);
+            global_state.ve_slope = global_state
+                .ve_slope
+                .saturating_sub(slope_schedule.slope_changes[index]);
+            slope_schedule.slope_changes[index] = 0;
+
+            global_state.ve_epoch_start = next_week;
+            global_state.ve_epoch_supply = current_ve_supply(global_state);
+        }
+
+        checkpoint_time = step_end;
+    }
+
+    global_state.ve_checkpoint_time = checkpoint_time;
+
+    Ok(())
+}
+
+/// Replaces a lock's old decay line with its new one in the global supply.
+/// The supply must already be checkpointed to `current_time`.
+pub fn update_ve_supply(
+    global_state: &mut GlobalState,
+    slope_schedule: &mut SlopeSchedule,
+    old_point: (u128, u128),
+    old_unlock_time: i64,
+    new_point: (u128, u128),
+    new_unlock_time: i64,
+) -> Result<()> {
+    let (old_bias, old_slope) = old_point;
+    let (new_bias, new_slope) = new_point;
+
+    global_state.ve_bias = global_state
+        .ve_bias
+        .checked_add(new_bias)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_sub(old_bias)
+        .ok_or(ErrorCode::MathOverflow)?;
+    global_state.ve_slope = global_state
+        .ve_slope
+        .checked_add(new_slope)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_sub(old_slope)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    if old_slope > 0 {
+        let index = slope_schedule_index(old_unlock_time);
+        slope_schedule.slope_changes[index] = slope_schedule.slope_changes[index]
+            .checked_sub(old_slope)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    if new_slope > 0 {
+        let index = slope_schedule_index(new_unlock_time);
+        slope_schedule.slope_changes[index] = slope_schedule.slope_changes[index]
+            .checked_add(new_slope)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    Ok(())
+}
+
+pub fn current_ve_supply(global_state: &GlobalState) -> u64 {
+    (global_state.ve_bias / VE_SLOPE_PRECISION) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: i64 = 100 * WEEK;
+
+    fn zeroed<T: AnchorDeserialize>() -> T {
+        T::deserialize(&mut &vec![0u8; 8192][..]).unwrap()
+    }
+
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+    }
+
+    impl TestAccount {
+        fn new<T: AccountSerialize>(key: Pubkey, account: &T) -> Self {
+            let mut data = Vec::new();
+            account.try_serialize(&mut data).unwrap();
+            Self { key, owner: crate::ID, lamports: 1, data }
+        }
+
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                false,
+                true,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                0,
+            )
+        }
+    }
+
+    fn fee_epoch_account(
+        base_mint: &Pubkey,
+        reward_mint: &Pubkey,
+        epoch_start: i64,
+        total_fees: u64,
+        ve_supply: u64,
+    ) -> TestAccount {
+        let (key, bump) = Pubkey::find_program_address(
+            &[FEE_EPOCH_SEED, base_mint.as_ref(), reward_mint.as_ref(), &epoch_start.to_le_bytes()],
+            &crate::ID,
+        );
+        let fee_epoch = FeeEpoch {
+            global_state: Pubkey::default(),
+            reward_mint: *reward_mint,
+            epoch_start,
+            total_fees,
+            ve_supply,
+            claimed_fees: 0,
+            bump,
+        };
+        TestAccount::new(key, &fee_epoch)
+    }
+
+    fn claimed_fees(account: &TestAccount) -> u64 {
+        FeeEpoch::try_deserialize(&mut &account.data[..]).unwrap().claimed_fees
+    }
+
+    fn test_lock(initial_ve_amount: u64, lock_start_time: i64, unlock_time: i64) -> UserLock {
+        let mut user_lock: UserLock = zeroed();
+        user_lock.initial_ve_amount = initial_ve_amount;
+        user_lock.lock_start_time = lock_start_time;
+        user_lock.unlock_time = unlock_time;
+        user_lock.last_point_time = lock_start_time;
+        user_lock
+    }
+
+    fn test_reward(user_lock_key: Pubkey, reward_mint: Pubkey, epoch_cursor: i64) -> UserReward {
+        UserReward {
+            user_lock: user_lock_key,
+            reward_mint,
+            epoch_cursor,
+            point_index: 0,
+            fees_claimed: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn checkpoint_records_decayed_supply_at_week_boundaries() {
+        let mut global_state: GlobalState = zeroed();
+        let mut slope_schedule: SlopeSchedule = zeroed();
+        let lock_start = START + 1_000;
+        let unlock_time = START + 4 * WEEK;
+
+        global_state.ve_checkpoint_time = lock_start;
+        let point = calculate_ve_point(1_000_000, lock_start, unlock_time, lock_start).unwrap();
+        update_ve_supply(&mut global_state, &mut slope_schedule, (0, 0), 0, point, unlock_time).unwrap();
+
+        checkpoint_ve_supply(&mut global_state, &mut slope_schedule, START + WEEK + 5).unwrap();
+        let (bias_at_week, _) = calculate_ve_point(1_000_000, lock_start, unlock_time, START + WEEK).unwrap();
+        assert_eq!(global_state.ve_epoch_start, START + WEEK);
+        assert_eq!(global_state.ve_epoch_supply, (bias_at_week / VE_SLOPE_PRECISION) as u64);
+        assert!(global_state.ve_epoch_supply.abs_diff(
+            calculate_current_ve_balance(1_000_000, lock_start, unlock_time, START + WEEK).unwrap()
+        ) <= 1);
+
+        // The slope is dropped at unlock and the supply stays at zero afterwards
+        checkpoint_ve_supply(&mut global_state, &mut slope_schedule, unlock_time + WEEK + 5).unwrap();
+        assert_eq!(global_state.ve_epoch_start, unlock_time + WEEK);
+        assert_eq!(global_state.ve_epoch_supply, 0);
+        assert_eq!(global_state.ve_slope, 0);
+        assert!(global_state.ve_bias < VE_SLOPE_PRECISION);
+        assert!(slope_schedule.slope_changes.iter().all(|change| *change == 0));
+    }
+
+    #[test]
+    fn stream_credits_idle_weeks_to_the_next_epoch_without_delaying_finish() {
+        let mut reward_token: RewardToken = zeroed();
+        reward_token.reward_rate = 10;
+        reward_token.period_finish = START + 3 * WEEK + 50;
+        // Credited through the end of the first week; nobody touched the mint in the second
+        reward_token.last_update_time = START + WEEK;
+
+        let mut fee_epoch: FeeEpoch = zeroed();
+        fee_epoch.epoch_start = START + 2 * WEEK;
+        fee_epoch.ve_supply = 1;
+
+        let streamed = advance_reward_stream(&mut reward_token, &mut fee_epoch, START + 2 * WEEK + 100).unwrap();
+        assert_eq!(streamed, 10 * 2 * WEEK as u64);
+        assert_eq!(fee_epoch.total_fees, streamed);
+        assert_eq!(reward_token.last_update_time, START + 3 * WEEK);
+        assert_eq!(reward_token.period_finish, START + 3 * WEEK + 50);
+
+        fee_epoch.epoch_start = START + 3 * WEEK;
+        fee_epoch.total_fees = 0;
+        let streamed = advance_reward_stream(&mut reward_token, &mut fee_epoch, START + 3 * WEEK + 100).unwrap();
+        assert_eq!(streamed, 10 * 50);
+        assert_eq!(reward_token.last_update_time, START + 3 * WEEK + 50);
+        assert_eq!(advance_reward_stream(&mut reward_token, &mut fee_epoch, START + 3 * WEEK + 200).unwrap(), 0);
+    }
+
+    #[test]
+    fn accrue_pays_share_of_closed_epochs_once() {
+        let base_mint = Pubkey::new_unique();
+        let reward_mint = Pubkey::new_unique();
+        let user_lock_key = Pubkey::new_unique();
+        let epoch = START + WEEK;
+
+        let user_lock = test_lock(1_000_000, START + 100, START + 10 * WEEK);
+        let mut user_reward = test_reward(user_lock_key, reward_mint, epoch);
+        let balance = calculate_current_ve_balance(1_000_000, START + 100, START + 10 * WEEK, epoch).unwrap();
+        let mut epoch_account = fee_epoch_account(&base_mint, &reward_mint, epoch, 1_000_000, 4 * balance);
+
+        // The epoch is still open
+        let accrued = accrue_epoch_rewards(
+            &user_lock,
+            &user_lock_key,
+            &mut user_reward,
+            &base_mint,
+            &[epoch_account.info()],
+            epoch + 10,
+            &crate::ID,
+        )
+        .unwrap();
+        assert_eq!(accrued, 0);
+        assert_eq!(user_reward.epoch_cursor, epoch);
+
+        let accrued = accrue_epoch_rewards(
+            &user_lock,
+            &user_lock_key,
+            &mut user_reward,
+            &base_mint,
+            &[epoch_account.info()],
+            epoch + WEEK + 10,
+            &crate::ID,
+        )
+        .unwrap();
+        assert_eq!(accrued, 250_000);
+        assert_eq!(claimed_fees(&epoch_account), 250_000);
+        assert_eq!(user_reward.epoch_cursor, epoch + WEEK);
+        assert_eq!(user_reward.fees_claimed, 250_000);
+
+        let accrued = accrue_epoch_rewards(
+            &user_lock,
+            &user_lock_key,
+            &mut user_reward,
+            &base_mint,
+            &[epoch_account.info()],
+            epoch + WEEK + 20,
+            &crate::ID,
+        )
+        .unwrap();
+        assert_eq!(accrued, 0);
+        assert_eq!(claimed_fees(&epoch_account), 250_000);
+    }
+
+    #[test]
+    fn accrue_rejects_fee_epoch_of_another_week() {
+        let base_mint = Pubkey::new_unique();
+        let reward_mint = Pubkey::new_unique();
+        let user_lock_key = Pubkey::new_unique();
+
+        let user_lock = test_lock(1_000_000, START + 100, START + 10 * WEEK);
+        let mut user_reward = test_reward(user_lock_key, reward_mint, START + WEEK);
+        let mut epoch_account = fee_epoch_account(&base_mint, &reward_mint, START + 2 * WEEK, 1_000_000, 1);
+
+        let result = accrue_epoch_rewards(
+            &user_lock,
+            &user_lock_key,
+            &mut user_reward,
+            &base_mint,
+            &[epoch_account.info()],
+            START + 3 * WEEK,
+            &crate::ID,
+        );
+        assert_eq!(result.unwrap_err(), ErrorCode::InvalidFeeEpoch.into());
+        assert_eq!(claimed_fees(&epoch_account), 0);
+    }
+
+    #[test]
+    fn failed_accrual_leaves_fee_epochs_untouched() {
+        let base_mint = Pubkey::new_unique();
+        let reward_mint = Pubkey::new_unique();
+        let user_lock_key = Pubkey::new_unique();
+
+        let user_lock = test_lock(1_000_000, START + 100, START + 10 * WEEK);
+        let mut user_reward = test_reward(user_lock_key, reward_mint, START + WEEK);
+        let mut first_account = fee_epoch_account(&base_mint, &reward_mint, START + WEEK, 1_000_000, 1_000_000);
+        // Listed for the second week but belongs to the third
+        let mut wrong_account = fee_epoch_account(&base_mint, &reward_mint, START + 3 * WEEK, 1_000_000, 1_000_000);
+
+        let result = accrue_epoch_rewards(
+            &user_lock,
+            &user_lock_key,
+            &mut user_reward,
+            &base_mint,
+            &[first_account.info(), wrong_account.info()],
+            START + 4 * WEEK,
+            &crate::ID,
+        );
+        assert_eq!(result.unwrap_err(), ErrorCode::InvalidFeeEpoch.into());
+        assert_eq!(claimed_fees(&first_account), 0);
+    }
+
+    #[test]
+    fn shared_run_stays_aligned_when_the_cursor_jumps() {
+        let base_mint = Pubkey::new_unique();
+        let reward_mint = Pubkey::new_unique();
+        let user_lock_key = Pubkey::new_unique();
+        let lock_time = START + WEEK + 100;
+        let unlock_time = START + 10 * WEEK;
+
+        // A fresh lock: the empty starting line, then the live line set a week after the mint's first epoch
+        let empty_point = LockPoint {
+            user_lock: user_lock_key,
+            index: 0,
+            initial_ve_amount: 0,
+            lock_start_time: 0,
+            unlock_time: 0,
+            set_time: 0,
+            bump: 0,
+        };
+        let mut point_account = TestAccount::new(Pubkey::new_unique(), &empty_point);
+        let mut user_lock = test_lock(1_000_000, lock_time, unlock_time);
+        user_lock.point_count = 1;
+        let mut user_reward = test_reward(user_lock_key, reward_mint, START);
+
+        let balance = calculate_current_ve_balance(1_000_000, lock_time, unlock_time, START + 2 * WEEK).unwrap();
+        let mut epoch_accounts: Vec<TestAccount> = (0..3)
+            .map(|week| fee_epoch_account(&base_mint, &reward_mint, START + week * WEEK, 1_000_000, 4 * balance))
+            .collect();
+
+        // Walking the run in order would price the lock's first week against the mint's first epoch
+        let point_infos = [point_account.info()];
+        let run_infos: Vec<AccountInfo> = epoch_accounts.iter_mut().map(TestAccount::info).collect();
+        let mut infos = point_infos.to_vec();
+        infos.extend_from_slice(&run_infos);
+        let result = accrue_epoch_rewards(
+            &user_lock,
+            &user_lock_key,
+            &mut user_reward.clone(),
+            &base_mint,
+            &infos,
+            START + 3 * WEEK + 10,
+            &crate::ID,
+        );
+        assert_eq!(result.unwrap_err(), ErrorCode::InvalidFeeEpoch.into());
+
+        let accrued = accrue_shared_epoch_rewards(
+            &user_lock,
+            &user_lock_key,
+            &mut user_reward,
+            &base_mint,
+            &point_infos,
+            &run_infos,
+            START,
+            START + 3 * WEEK + 10,
+            &crate::ID,
+        )
+        .unwrap();
+        drop((infos, run_infos, point_infos));
+
+        assert_eq!(accrued, 250_000);
+        assert_eq!(claimed_fees(&epoch_accounts[0]), 0);
+        assert_eq!(claimed_fees(&epoch_accounts[1]), 0, "The lock was not in the supply its week opened with");
+        assert_eq!(claimed_fees(&epoch_accounts[2]), 250_000);
+        assert_eq!(user_reward.epoch_cursor, START + 3 * WEEK);
+        assert_eq!(user_reward.point_index, 1);
+    }
+
+    #[test]
+    fn top_up_does_not_capture_earlier_fees() {
+        let base_mint = Pubkey::new_unique();
+        let reward_mint = Pubkey::new_unique();
+        let user_lock_key = Pubkey::new_unique();
+        let first_epoch = START + WEEK;
+        let second_epoch = START + 2 * WEEK;
+        let unlock_time = START + 10 * WEEK;
+        let top_up_time = first_epoch + 500;
+
+        // The original line was archived when the lock was topped up after the first epoch opened
+        let original_point = LockPoint {
+            user_lock: user_lock_key,
+            index: 0,
+            initial_ve_amount: 100_000,
+            lock_start_time: START + 100,
+            unlock_time,
+            set_time: START + 100,
+            bump: 0,
+        };
+        let mut point_account = TestAccount::new(Pubkey::new_unique(), &original_point);
+
+        let mut user_lock = test_lock(900_000, top_up_time, unlock_time);
+        user_lock.point_count = 1;
+        let mut user_reward = test_reward(user_lock_key, reward_mint, first_epoch);
+
+        let original_balance = calculate_current_ve_balance(100_000, START + 100, unlock_time, first_epoch).unwrap();
+        let topped_up_balance = calculate_current_ve_balance(900_000, top_up_time, unlock_time, second_epoch).unwrap();
+        let mut first_account = fee_epoch_account(&base_mint, &reward_mint, first_epoch, 1_000_000, 4 * original_balance);
+        let mut second_account =
+            fee_epoch_account(&base_mint, &reward_mint, second_epoch, 1_000_000, 2 * topped_up_balance);
+
+        let accrued = accrue_epoch_rewards(
+            &user_lock,
+            &user_lock_key,
+            &mut user_reward,
+            &base_mint,
+            &[point_account.info(), first_account.info(), second_account.info()],
+            START + 3 * WEEK + 10,
+            &crate::ID,
+        )
+        .unwrap();
+
+        assert_eq!(claimed_fees(&first_account), 250_000);
+        assert_eq!(claimed_fees(&second_account), 500_000);
+        assert_eq!(accrued, 750_000);
+        assert_eq!(user_reward.epoch_cursor, START + 3 * WEEK);
+        assert_eq!(user_reward.point_index, 1);
+    }
+}
@@ -46,17 +46,27 @@ async function main() {
   const veMintKeypair = Keypair.generate();
 
   const [globalState] = PublicKey.findProgramAddressSync(
-    [Buffer.from("global-state")],
+    [Buffer.from("global-state"), baseMintKeypair.publicKey.toBuffer()],
     programId
   );
 
   const [tokenVault] = PublicKey.findProgramAddressSync(
-    [Buffer.from("token-vault")],
+    [Buffer.from("token-vault"), baseMintKeypair.publicKey.toBuffer()],
     programId
   );
 
   const [feeVault] = PublicKey.findProgramAddressSync(
-    [Buffer.from("fee-vault")],
+    [Buffer.from("fee-vault"), baseMintKeypair.publicKey.toBuffer(), baseMintKeypair.publicKey.toBuffer()],
+    programId
+  );
+
+  const [rewardToken] = PublicKey.findProgramAddressSync(
+    [Buffer.from("reward-token"), baseMintKeypair.publicKey.toBuffer(), baseMintKeypair.publicKey.toBuffer()],
+    programId
+  );
+
+  const [slopeSchedule] = PublicKey.findProgramAddressSync(
+    [Buffer.from("slope-schedule"), baseMintKeypair.publicKey.toBuffer()],
     programId
   );
 
@@ -82,9 +92,12 @@ async function main() {
 
   const lockMultiplierNumerator = new anchor.BN(4);
   const lockMultiplierDenominator = new anchor.BN(1);
+  const maxLockMultiplier = new anchor.BN(4);
+  const minLockDuration = new anchor.BN(7 * 24 * 60 * 60);
+  const maxLockDuration = new anchor.BN(4 * 365 * 24 * 60 * 60);
 
   const tx = await program.methods
-    .initialize(lockMultiplierNumerator, lockMultiplierDenominator)
+    .initialize(lockMultiplierNumerator, lockMultiplierDenominator, maxLockMultiplier, minLockDuration, maxLockDuration)
     .accountsStrict({
       authority: provider.wallet.publicKey,
       globalState,
@@ -92,6 +105,8 @@ async function main() {
       veMint: veMintKeypair.publicKey,
       tokenVault,
       feeVault,
+      slopeSchedule,
+      rewardToken,
       tokenProgram: TOKEN_2022_PROGRAM_ID,
       systemProgram: anchor.web3.SystemProgram.programId,
     })
@@ -110,10 +125,16 @@ async function main() {
     veMint: veMintKeypair.publicKey.toString(),
     tokenVault: tokenVault.toString(),
     feeVault: feeVault.toString(),
+    slopeSchedule: slopeSchedule.toString(),
+    rewardToken: rewardToken.toString(),
     lockMultiplier: {
       numerator: lockMultiplierNumerator.toString(),
       denominator: lockMultiplierDenominator.toString(),
     },
+    lockDuration: {
+      min: minLockDuration.toString(),
+      max: maxLockDuration.toString(),
+    },
     transactionSignature: tx,
     timestamp: new Date().toISOString(),
   };
@@ -0,0 +1,422 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { SolanaFractionalOwnershipToken } from "../target/types/solana_fractional_ownership_token";
+import IDL from "../target/idl/solana_fractional_ownership_token.json";
+import { PublicKey, Keypair, SystemProgram, Transaction, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  AccountLayout,
+  MINT_SIZE,
+  MintLayout,
+  getAssociatedTokenAddressSync,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createInitializeMint2Instruction,
+  createMintToInstruction,
+} from "@solana/spl-token";
+import { startAnchor, Clock, ProgramTestContext } from "solana-bankrun";
+import { BankrunProvider } from "anchor-bankrun";
+import { assert } from "chai";
+
+// Fees only pay out once their week has closed, so these tests move the bank clock
+describe("Fee payouts from closed epochs", () => {
+  const SECONDS_PER_DAY = 24 * 60 * 60;
+  const WEEK = 7 * SECONDS_PER_DAY;
+  const MIN_LOCK_DURATION = 7 * SECONDS_PER_DAY;
+  const MAX_LOCK_DURATION = 4 * 365 * SECONDS_PER_DAY;
+  const FIRST_WEEK = 2900 * WEEK;
+
+  const user1 = Keypair.generate();
+  const user2 = Keypair.generate();
+  const user3 = Keypair.generate();
+  const baseMintKeypair = Keypair.generate();
+  const veMintKeypair = Keypair.generate();
+  const baseMint = baseMintKeypair.publicKey;
+  const veMint = veMintKeypair.publicKey;
+
+  let context: ProgramTestContext;
+  let provider: BankrunProvider;
+  let program: Program<SolanaFractionalOwnershipToken>;
+  let authority: Keypair;
+
+  const pda = (...seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const le = (value: number) => new anchor.BN(value).toArrayLike(Buffer, "le", 8);
+
+  const globalState = () => pda(Buffer.from("global-state"), baseMint.toBuffer());
+  const tokenVault = () => pda(Buffer.from("token-vault"), baseMint.toBuffer());
+  const slopeSchedule = () => pda(Buffer.from("slope-schedule"), baseMint.toBuffer());
+  const feeVault = () => pda(Buffer.from("fee-vault"), baseMint.toBuffer(), baseMint.toBuffer());
+  const rewardToken = () => pda(Buffer.from("reward-token"), baseMint.toBuffer(), baseMint.toBuffer());
+  const feeEpochFor = (epochStart: number) =>
+    pda(Buffer.from("fee-epoch"), baseMint.toBuffer(), baseMint.toBuffer(), le(epochStart));
+  const userLockFor = (owner: PublicKey, positionId = 0) =>
+    pda(Buffer.from("user-lock"), baseMint.toBuffer(), owner.toBuffer(), le(positionId));
+  const positionMintFor = (userLock: PublicKey) => pda(Buffer.from("position-mint"), userLock.toBuffer());
+  const veEscrowFor = (userLock: PublicKey) => pda(Buffer.from("ve-escrow"), userLock.toBuffer());
+  const userRewardFor = (userLock: PublicKey) => pda(Buffer.from("user-reward"), userLock.toBuffer(), baseMint.toBuffer());
+  const lockPointAt = (userLock: PublicKey, index: number) => pda(Buffer.from("lock-point"), userLock.toBuffer(), le(index));
+  const tokenAccountFor = (owner: PublicKey) => getAssociatedTokenAddressSync(baseMint, owner, false, TOKEN_2022_PROGRAM_ID);
+  const positionTokenAccountFor = (owner: PublicKey, userLock: PublicKey) =>
+    getAssociatedTokenAddressSync(positionMintFor(userLock), owner, false, TOKEN_2022_PROGRAM_ID);
+
+  const warpTo = async (unixTimestamp: number) => {
+    const clock = await context.banksClient.getClock();
+    const slot = clock.slot + 100n;
+    context.warpToSlot(slot);
+    context.setClock(new Clock(slot, clock.epochStartTimestamp, clock.epoch, clock.leaderScheduleEpoch, BigInt(unixTimestamp)));
+  };
+
+  const balanceOf = async (tokenAccount: PublicKey) => {
+    const account = await context.banksClient.getAccount(tokenAccount);
+    return AccountLayout.decode(Buffer.from(account!.data)).amount;
+  };
+
+  // Mirrors calculate_current_ve_balance at an epoch boundary
+  const veBalanceAt = async (userLock: PublicKey, epochStart: number) => {
+    const lock = await program.account.userLock.fetch(userLock);
+    const remaining = lock.unlockTime.toNumber() - epochStart;
+    const duration = lock.unlockTime.sub(lock.lockStartTime);
+    return lock.initialVeAmount.mul(new anchor.BN(remaining)).div(duration);
+  };
+
+  const expectedShare = async (userLock: PublicKey, epochStart: number) => {
+    const epoch = await program.account.feeEpoch.fetch(feeEpochFor(epochStart));
+    const share = epoch.totalFees.mul(await veBalanceAt(userLock, epochStart)).div(epoch.veSupply);
+    return BigInt(share.toString());
+  };
+
+  const lock = async (user: Keypair, amount: number, epochStart: number, positionId = 0) => {
+    const userLock = userLockFor(user.publicKey, positionId);
+    await program.methods
+      .lockTokens(new anchor.BN(positionId), new anchor.BN(amount), new anchor.BN(MAX_LOCK_DURATION))
+      .accountsStrict({
+        user: user.publicKey,
+        userLock,
+        positionMint: positionMintFor(userLock),
+        positionTokenAccount: positionTokenAccountFor(user.publicKey, userLock),
+        lockPoint: lockPointAt(userLock, 0),
+        globalState: globalState(),
+        baseMint,
+        veMint,
+        userTokenAccount: tokenAccountFor(user.publicKey),
+        veEscrow: veEscrowFor(userLock),
+        tokenVault: tokenVault(),
+        slopeSchedule: slopeSchedule(),
+        rewardToken: rewardToken(),
+        feeEpoch: feeEpochFor(epochStart),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        veTokenProgram: TOKEN_2022_PROGRAM_ID,
+        positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+  };
+
+  const deposit = (amount: number, epochStart: number) =>
+    program.methods
+      .depositFees(new anchor.BN(amount))
+      .accountsStrict({
+        depositor: authority.publicKey,
+        payer: authority.publicKey,
+        globalState: globalState(),
+        rewardToken: rewardToken(),
+        rewardMint: baseMint,
+        depositorTokenAccount: tokenAccountFor(authority.publicKey),
+        treasuryTokenAccount: null,
+        feeVault: feeVault(),
+        slopeSchedule: slopeSchedule(),
+        feeEpoch: feeEpochFor(epochStart),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+  // `history` is the lock points from the cursor's point_index, then the fee epochs it will price
+  const claim = (user: Keypair, epochStart: number, history: PublicKey[]) => {
+    const userLock = userLockFor(user.publicKey);
+    return program.methods
+      .claimFees()
+      .accountsStrict({
+        user: user.publicKey,
+        userLock,
+        positionTokenAccount: positionTokenAccountFor(user.publicKey, userLock),
+        userReward: userRewardFor(userLock),
+        globalState: globalState(),
+        slopeSchedule: slopeSchedule(),
+        rewardToken: rewardToken(),
+        rewardMint: baseMint,
+        userTokenAccount: tokenAccountFor(user.publicKey),
+        feeVault: feeVault(),
+        feeEpoch: feeEpochFor(epochStart),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(history.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })))
+      .signers([user])
+      .rpc();
+  };
+
+  // One `[user_lock, user_reward, position, recipient, lock points...]` group per lock
+  const batchClaim = (epochStarts: number[], currentEpoch: number, entries: { user: Keypair; points: PublicKey[] }[]) =>
+    program.methods
+      .claimFeesBatch(new anchor.BN(epochStarts[0]), epochStarts.length, Buffer.from(entries.map(({ points }) => points.length)))
+      .accountsStrict({
+        caller: authority.publicKey,
+        globalState: globalState(),
+        slopeSchedule: slopeSchedule(),
+        rewardToken: rewardToken(),
+        rewardMint: baseMint,
+        feeVault: feeVault(),
+        feeEpoch: feeEpochFor(currentEpoch),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([
+        ...epochStarts.map((epochStart) => ({ pubkey: feeEpochFor(epochStart), isWritable: true, isSigner: false })),
+        ...entries.flatMap(({ user, points }) => {
+          const userLock = userLockFor(user.publicKey);
+          return [
+            { pubkey: userLock, isWritable: false, isSigner: false },
+            { pubkey: userRewardFor(userLock), isWritable: true, isSigner: false },
+            { pubkey: positionTokenAccountFor(user.publicKey, userLock), isWritable: false, isSigner: false },
+            { pubkey: tokenAccountFor(user.publicKey), isWritable: true, isSigner: false },
+            ...points.map((pubkey) => ({ pubkey, isWritable: false, isSigner: false })),
+          ];
+        }),
+      ])
+      .rpc();
+
+  before(async () => {
+    const funded = { lamports: 10 * LAMPORTS_PER_SOL, data: Buffer.alloc(0), owner: SystemProgram.programId, executable: false };
+    context = await startAnchor(
+      ".",
+      [],
+      [user1, user2, user3].map((user) => ({ address: user.publicKey, info: funded }))
+    );
+    provider = new BankrunProvider(context);
+    program = new Program<SolanaFractionalOwnershipToken>(IDL as SolanaFractionalOwnershipToken, provider);
+    authority = context.payer;
+
+    await warpTo(FIRST_WEEK + 3600);
+
+    const rent = await context.banksClient.getRent();
+    const setup = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: authority.publicKey,
+        newAccountPubkey: baseMint,
+        space: MINT_SIZE,
+        lamports: Number(rent.minimumBalance(BigInt(MINT_SIZE))),
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      createInitializeMint2Instruction(baseMint, 9, authority.publicKey, null, TOKEN_2022_PROGRAM_ID)
+    );
+    for (const owner of [authority.publicKey, user1.publicKey, user2.publicKey, user3.publicKey]) {
+      setup.add(
+        createAssociatedTokenAccountIdempotentInstruction(authority.publicKey, tokenAccountFor(owner), owner, baseMint, TOKEN_2022_PROGRAM_ID),
+        createMintToInstruction(baseMint, tokenAccountFor(owner), authority.publicKey, 10_000n * 10n ** 9n, [], TOKEN_2022_PROGRAM_ID)
+      );
+    }
+    await provider.sendAndConfirm(setup, [baseMintKeypair]);
+
+    await program.methods
+      .initializeWithMint(new anchor.BN(4), new anchor.BN(1), new anchor.BN(4), new anchor.BN(MIN_LOCK_DURATION), new anchor.BN(MAX_LOCK_DURATION))
+      .accountsStrict({
+        authority: authority.publicKey,
+        globalState: globalState(),
+        baseMint,
+        veMint,
+        tokenVault: tokenVault(),
+        feeVault: feeVault(),
+        slopeSchedule: slopeSchedule(),
+        rewardToken: rewardToken(),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        veTokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([veMintKeypair])
+      .rpc();
+  });
+
+  it("Pays each lock its ve share of a closed epoch and nothing to a later lock", async () => {
+    const lockWeek = FIRST_WEEK + WEEK;
+    const feeWeek = lockWeek + WEEK;
+    const claimWeek = feeWeek + WEEK;
+
+    await warpTo(lockWeek + 3600);
+    await lock(user1, 300 * 10 ** 9, lockWeek);
+    await lock(user2, 100 * 10 ** 9, lockWeek);
+
+    await warpTo(feeWeek + 3600);
+    await deposit(1000 * 10 ** 9, feeWeek);
+    // Locked after the epoch's supply was recorded
+    await lock(user3, 100 * 10 ** 9, feeWeek);
+
+    const epoch = await program.account.feeEpoch.fetch(feeEpochFor(feeWeek));
+    assert.equal(epoch.totalFees.toString(), (1000 * 10 ** 9).toString());
+
+    try {
+      await claim(user1, feeWeek, [lockPointAt(userLockFor(user1.publicKey), 0), feeEpochFor(feeWeek)]);
+      assert.fail("Expected NoFeesToClaim while the epoch is open");
+    } catch (err) {
+      assert.include(err.toString(), "NoFeesToClaim");
+    }
+
+    await warpTo(claimWeek + 3600);
+
+    // The first line is the empty one archived at lock time, so the cursor skips to the week after the lock
+    const payouts: bigint[] = [];
+    for (const user of [user1, user2]) {
+      const userLock = userLockFor(user.publicKey);
+      const expected = await expectedShare(userLock, feeWeek);
+      const before = await balanceOf(tokenAccountFor(user.publicKey));
+
+      await claim(user, claimWeek, [lockPointAt(userLock, 0), feeEpochFor(feeWeek)]);
+
+      const paid = (await balanceOf(tokenAccountFor(user.publicKey))) - before;
+      assert.equal(paid, expected);
+      payouts.push(paid);
+
+      const reward = await program.account.userReward.fetch(userRewardFor(userLock));
+      assert.equal(reward.epochCursor.toNumber(), claimWeek);
+      assert.equal(reward.feesClaimed.toString(), paid.toString());
+    }
+
+    assert.isTrue(payouts[0] > 2n * payouts[1], "Three times the ve earns three times the fees");
+    const claimed = (await program.account.feeEpoch.fetch(feeEpochFor(feeWeek))).claimedFees;
+    assert.equal(claimed.toString(), (payouts[0] + payouts[1]).toString());
+    assert.isTrue(payouts[0] + payouts[1] <= 1000n * 10n ** 9n);
+
+    for (const user of [user1, user3]) {
+      try {
+        await claim(user, claimWeek, [lockPointAt(userLockFor(user.publicKey), 0)]);
+        assert.fail("Expected NoFeesToClaim");
+      } catch (err) {
+        assert.include(err.toString(), "NoFeesToClaim");
+      }
+    }
+
+    console.log("✓ Closed epoch paid 3:1 by ve; a lock taken after the deposit earned nothing");
+  });
+
+  it("Batch claim pays a closed epoch to locks whose cursors start at different weeks", async () => {
+    const batchWeek = FIRST_WEEK + 3 * WEEK;
+    const currentWeek = batchWeek + WEEK;
+    await deposit(1000 * 10 ** 9, batchWeek);
+    await warpTo(currentWeek + 3600);
+
+    // user3 never claimed, so its cursor still sits at the mint's first epoch and jumps to the week after its lock
+    const lock1 = userLockFor(user1.publicKey);
+    const lock3 = userLockFor(user3.publicKey);
+    const run = [0, 1, 2, 3].map((week) => FIRST_WEEK + week * WEEK);
+    const expected = new Map<Keypair, bigint>();
+    const balancesBefore = new Map<Keypair, bigint>();
+    for (const user of [user1, user2, user3]) {
+      expected.set(user, await expectedShare(userLockFor(user.publicKey), batchWeek));
+      balancesBefore.set(user, await balanceOf(tokenAccountFor(user.publicKey)));
+    }
+    const paid = async (user: Keypair) => (await balanceOf(tokenAccountFor(user.publicKey))) - balancesBefore.get(user)!;
+
+    // user3's entry lists another lock's point: it is skipped and the rest are still paid
+    await batchClaim(run, currentWeek, [
+      { user: user1, points: [] },
+      { user: user2, points: [] },
+      { user: user3, points: [lockPointAt(lock1, 0)] },
+    ]);
+
+    assert.equal(await paid(user1), expected.get(user1)!);
+    assert.equal(await paid(user2), expected.get(user2)!);
+    assert.equal(await paid(user3), 0n);
+    assert.isNull(await context.banksClient.getAccount(userRewardFor(lock3)), "A skipped entry records nothing");
+
+    await batchClaim(run, currentWeek, [{ user: user3, points: [lockPointAt(lock3, 0)] }]);
+
+    assert.isTrue(expected.get(user3)! > 0n);
+    assert.equal(await paid(user3), expected.get(user3)!);
+    const reward3 = await program.account.userReward.fetch(userRewardFor(lock3));
+    assert.equal(reward3.epochCursor.toNumber(), currentWeek);
+
+    const epoch = await program.account.feeEpoch.fetch(feeEpochFor(batchWeek));
+    assert.equal(
+      epoch.claimedFees.toString(),
+      (expected.get(user1)! + expected.get(user2)! + expected.get(user3)!).toString()
+    );
+
+    console.log("✓ Batch crank paid three locks from one shared run of epochs");
+  });
+
+  it("Merge settles the source's closed epochs before closing it", async () => {
+    const sourceWeek = FIRST_WEEK + 4 * WEEK;
+    const feeWeek = sourceWeek + WEEK;
+    const mergeWeek = feeWeek + WEEK;
+    const destination = userLockFor(user1.publicKey);
+    const source = userLockFor(user1.publicKey, 1);
+
+    await lock(user1, 50 * 10 ** 9, sourceWeek, 1);
+    await warpTo(feeWeek + 3600);
+    await deposit(1000 * 10 ** 9, feeWeek);
+    await warpTo(mergeWeek + 3600);
+
+    const merge = async (sourceHistory: PublicKey[]) => {
+      const { pointCount } = await program.account.userLock.fetch(destination);
+      return program.methods
+        .mergeLocks(sourceHistory.length)
+        .accountsStrict({
+          user: user1.publicKey,
+          userLock: destination,
+          positionTokenAccount: positionTokenAccountFor(user1.publicKey, destination),
+          sourceLock: source,
+          sourcePositionMint: positionMintFor(source),
+          sourcePositionTokenAccount: positionTokenAccountFor(user1.publicKey, source),
+          lockPoint: lockPointAt(destination, pointCount.toNumber()),
+          userReward: userRewardFor(destination),
+          sourceUserReward: userRewardFor(source),
+          globalState: globalState(),
+          slopeSchedule: slopeSchedule(),
+          rewardToken: rewardToken(),
+          rewardMint: baseMint,
+          userTokenAccount: tokenAccountFor(user1.publicKey),
+          feeVault: feeVault(),
+          feeEpoch: feeEpochFor(mergeWeek),
+          veMint,
+          veEscrow: veEscrowFor(destination),
+          sourceVeEscrow: veEscrowFor(source),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          veTokenProgram: TOKEN_2022_PROGRAM_ID,
+          positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(sourceHistory.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })))
+        .signers([user1])
+        .rpc();
+    };
+
+    // The source's cursor is closed with it, so its closed epochs can't be left behind
+    try {
+      await merge([]);
+      assert.fail("Expected UnsettledFees");
+    } catch (err) {
+      assert.include(err.toString(), "UnsettledFees");
+    }
+
+    const expected = await expectedShare(source, feeWeek);
+    const destinationBefore = await program.account.userLock.fetch(destination);
+    const balanceBefore = await balanceOf(tokenAccountFor(user1.publicKey));
+
+    await merge([lockPointAt(source, 0), feeEpochFor(feeWeek)]);
+
+    assert.isTrue(expected > 0n);
+    assert.equal((await balanceOf(tokenAccountFor(user1.publicKey))) - balanceBefore, expected);
+    const merged = await program.account.userLock.fetch(destination);
+    assert.equal(merged.lockedAmount.sub(destinationBefore.lockedAmount).toString(), (50 * 10 ** 9).toString());
+
+    for (const closed of [source, userRewardFor(source), veEscrowFor(source)]) {
+      assert.isNull(await context.banksClient.getAccount(closed));
+    }
+    const nft = await context.banksClient.getAccount(positionMintFor(source));
+    assert.equal(MintLayout.decode(Buffer.from(nft!.data)).supply, 0n, "Source position NFT is burned");
+
+    console.log("✓ Merge paid the source's closed epoch and closed the source");
+  });
+});
@@ -2,7 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { SolanaFractionalOwnershipToken } from "../target/types/solana_fractional_ownership_token";
 import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
-import { TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync, createAssociatedTokenAccountInstruction } from "@solana/spl-token";
+import { TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync, createAssociatedTokenAccountInstruction, createAccount, createMint, getMint, getOrCreateAssociatedTokenAccount, mintTo, transferChecked } from "@solana/spl-token";
 import { assert } from "chai";
 
 describe("Fractional Ownership veToken System", () => {
@@ -22,20 +22,79 @@ describe("Fractional Ownership veToken System", () => {
   let globalState: PublicKey;
   let tokenVault: PublicKey;
   let feeVault: PublicKey;
+  let rewardToken: PublicKey;
+  let slopeSchedule: PublicKey;
+  let faucetState: PublicKey;
+  let usdcMint: PublicKey;
+
+  const faucetClaimFor = (wallet: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("faucet-claim"), baseMint.toBuffer(), wallet.toBuffer()], program.programId)[0];
 
   const SECONDS_PER_DAY = 24 * 60 * 60;
+  const WEEK = 7 * SECONDS_PER_DAY;
   const MIN_LOCK_DURATION = 7 * SECONDS_PER_DAY;
   const MAX_LOCK_DURATION = 4 * 365 * SECONDS_PER_DAY;
 
+  const feeEpochFor = (epochStart: number, rewardMint: PublicKey = baseMint) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("fee-epoch"), baseMint.toBuffer(), rewardMint.toBuffer(), new anchor.BN(epochStart).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const rewardTokenFor = (rewardMint: PublicKey, base: PublicKey = baseMint) =>
+    PublicKey.findProgramAddressSync([Buffer.from("reward-token"), base.toBuffer(), rewardMint.toBuffer()], program.programId)[0];
+
+  const feeVaultFor = (rewardMint: PublicKey, base: PublicKey = baseMint) =>
+    PublicKey.findProgramAddressSync([Buffer.from("fee-vault"), base.toBuffer(), rewardMint.toBuffer()], program.programId)[0];
+
+  const userLockFor = (owner: PublicKey, positionId = 0) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("user-lock"), baseMint.toBuffer(), owner.toBuffer(), new anchor.BN(positionId).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const positionMintFor = (userLock: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("position-mint"), userLock.toBuffer()], program.programId)[0];
+
+  // Holder's token account for a position NFT
+  const positionTokenAccountFor = (holder: PublicKey, userLock: PublicKey) =>
+    getAssociatedTokenAddressSync(positionMintFor(userLock), holder, false, TOKEN_2022_PROGRAM_ID);
+
+  // Each position's veTokens sit in an escrow owned by the program, so they follow the NFT
+  const veEscrowFor = (userLock: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("ve-escrow"), userLock.toBuffer()], program.programId)[0];
+
+  const userRewardFor = (userLock: PublicKey, rewardMint: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("user-reward"), userLock.toBuffer(), rewardMint.toBuffer()], program.programId)[0];
+
+  const lockPointAt = (userLock: PublicKey, index: number) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("lock-point"), userLock.toBuffer(), new anchor.BN(index).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  // Every lock change archives the live decay line at index `pointCount`
+  const nextLockPoint = async (userLock: PublicKey) => {
+    const existing = await program.account.userLock.fetchNullable(userLock);
+    return lockPointAt(userLock, existing ? existing.pointCount.toNumber() : 0);
+  };
+
+  const currentEpochStart = async () => {
+    const blockTime = await provider.connection.getBlockTime(await provider.connection.getSlot());
+    return Math.floor(blockTime / WEEK) * WEEK;
+  };
+
   before(async () => {
     baseMintKeypair = Keypair.generate();
     veMintKeypair = Keypair.generate();
     baseMint = baseMintKeypair.publicKey;
     veMint = veMintKeypair.publicKey;
 
-    [globalState] = PublicKey.findProgramAddressSync([Buffer.from("global-state")], program.programId);
-    [tokenVault] = PublicKey.findProgramAddressSync([Buffer.from("token-vault")], program.programId);
-    [feeVault] = PublicKey.findProgramAddressSync([Buffer.from("fee-vault")], program.programId);
+    [globalState] = PublicKey.findProgramAddressSync([Buffer.from("global-state"), baseMint.toBuffer()], program.programId);
+    [tokenVault] = PublicKey.findProgramAddressSync([Buffer.from("token-vault"), baseMint.toBuffer()], program.programId);
+    feeVault = feeVaultFor(baseMint);
+    rewardToken = rewardTokenFor(baseMint);
+    [slopeSchedule] = PublicKey.findProgramAddressSync([Buffer.from("slope-schedule"), baseMint.toBuffer()], program.programId);
 
     const airdropUser1 = await provider.connection.requestAirdrop(user1.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL);
     const airdropUser2 = await provider.connection.requestAirdrop(user2.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL);
@@ -49,7 +108,7 @@ describe("Fractional Ownership veToken System", () => {
     const lockMultiplierDenominator = new anchor.BN(1);
 
     await program.methods
-      .initialize(lockMultiplierNumerator, lockMultiplierDenominator)
+      .initialize(lockMultiplierNumerator, lockMultiplierDenominator, new anchor.BN(4), new anchor.BN(MIN_LOCK_DURATION), new anchor.BN(MAX_LOCK_DURATION))
       .accountsStrict({
         authority: authority.publicKey,
         globalState,
@@ -57,6 +116,8 @@ describe("Fractional Ownership veToken System", () => {
         veMint,
         tokenVault,
         feeVault,
+        slopeSchedule,
+        rewardToken,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
@@ -71,6 +132,223 @@ describe("Fractional Ownership veToken System", () => {
     console.log("✓ Protocol initialized with 4x max lock multiplier");
   });
 
+  it("Initializes a second independent instance from the same program", async () => {
+    const otherBaseMintKeypair = Keypair.generate();
+    const otherVeMintKeypair = Keypair.generate();
+    const otherBaseMint = otherBaseMintKeypair.publicKey;
+
+    const [otherGlobalState] = PublicKey.findProgramAddressSync([Buffer.from("global-state"), otherBaseMint.toBuffer()], program.programId);
+    const [otherTokenVault] = PublicKey.findProgramAddressSync([Buffer.from("token-vault"), otherBaseMint.toBuffer()], program.programId);
+    const otherFeeVault = feeVaultFor(otherBaseMint, otherBaseMint);
+    const otherRewardToken = rewardTokenFor(otherBaseMint, otherBaseMint);
+    const [otherSlopeSchedule] = PublicKey.findProgramAddressSync([Buffer.from("slope-schedule"), otherBaseMint.toBuffer()], program.programId);
+
+    await program.methods
+      .initialize(new anchor.BN(2), new anchor.BN(1), new anchor.BN(2), new anchor.BN(MIN_LOCK_DURATION), new anchor.BN(365 * SECONDS_PER_DAY))
+      .accountsStrict({
+        authority: authority.publicKey,
+        globalState: otherGlobalState,
+        baseMint: otherBaseMint,
+        veMint: otherVeMintKeypair.publicKey,
+        tokenVault: otherTokenVault,
+        feeVault: otherFeeVault,
+        slopeSchedule: otherSlopeSchedule,
+        rewardToken: otherRewardToken,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([otherBaseMintKeypair, otherVeMintKeypair])
+      .rpc();
+
+    const otherState = await program.account.globalState.fetch(otherGlobalState);
+    const firstState = await program.account.globalState.fetch(globalState);
+    assert.equal(otherState.baseMint.toString(), otherBaseMint.toString());
+    assert.equal(otherState.lockMultiplierNumerator.toNumber(), 2);
+    assert.equal(otherState.maxLockMultiplier.toNumber(), 2);
+    assert.equal(otherState.maxLockDuration.toNumber(), 365 * SECONDS_PER_DAY);
+    assert.equal(firstState.lockMultiplierNumerator.toNumber(), 4);
+    assert.equal(firstState.maxLockDuration.toNumber(), MAX_LOCK_DURATION);
+
+    console.log("✓ Second instance (1-year max) initialized alongside the first (4-year max)");
+  });
+
+  it("Rejects invalid lock configuration at initialize", async () => {
+    const badBaseMintKeypair = Keypair.generate();
+    const badVeMintKeypair = Keypair.generate();
+    const badBaseMint = badBaseMintKeypair.publicKey;
+
+    const [badGlobalState] = PublicKey.findProgramAddressSync([Buffer.from("global-state"), badBaseMint.toBuffer()], program.programId);
+    const [badTokenVault] = PublicKey.findProgramAddressSync([Buffer.from("token-vault"), badBaseMint.toBuffer()], program.programId);
+    const badFeeVault = feeVaultFor(badBaseMint, badBaseMint);
+    const badRewardToken = rewardTokenFor(badBaseMint, badBaseMint);
+    const [badSlopeSchedule] = PublicKey.findProgramAddressSync([Buffer.from("slope-schedule"), badBaseMint.toBuffer()], program.programId);
+
+    const accounts = {
+      authority: authority.publicKey,
+      globalState: badGlobalState,
+      baseMint: badBaseMint,
+      veMint: badVeMintKeypair.publicKey,
+      tokenVault: badTokenVault,
+      feeVault: badFeeVault,
+      slopeSchedule: badSlopeSchedule,
+      rewardToken: badRewardToken,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+
+    try {
+      await program.methods
+        .initialize(new anchor.BN(1), new anchor.BN(2), new anchor.BN(4), new anchor.BN(MIN_LOCK_DURATION), new anchor.BN(MAX_LOCK_DURATION))
+        .accountsStrict(accounts)
+        .signers([badBaseMintKeypair, badVeMintKeypair])
+        .rpc();
+      assert.fail("Expected InvalidLockMultiplier");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidLockMultiplier");
+    }
+
+    try {
+      await program.methods
+        .initialize(new anchor.BN(3), new anchor.BN(1), new anchor.BN(2), new anchor.BN(MIN_LOCK_DURATION), new anchor.BN(MAX_LOCK_DURATION))
+        .accountsStrict(accounts)
+        .signers([badBaseMintKeypair, badVeMintKeypair])
+        .rpc();
+      assert.fail("Expected InvalidLockMultiplier above the instance cap");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidLockMultiplier");
+    }
+
+    try {
+      await program.methods
+        .initialize(new anchor.BN(4), new anchor.BN(1), new anchor.BN(4), new anchor.BN(MAX_LOCK_DURATION), new anchor.BN(MIN_LOCK_DURATION))
+        .accountsStrict(accounts)
+        .signers([badBaseMintKeypair, badVeMintKeypair])
+        .rpc();
+      assert.fail("Expected InvalidLockDurationBounds");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidLockDurationBounds");
+    }
+
+    console.log("✓ Inverted or over-cap multiplier and inverted duration bounds rejected");
+  });
+
+  it("Initializes an instance against an existing mint", async () => {
+    const existingMint = await createMint(
+      provider.connection,
+      authority.payer,
+      authority.publicKey,
+      null,
+      6,
+      Keypair.generate(),
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const existingVeMintKeypair = Keypair.generate();
+
+    const [existingGlobalState] = PublicKey.findProgramAddressSync([Buffer.from("global-state"), existingMint.toBuffer()], program.programId);
+    const [existingTokenVault] = PublicKey.findProgramAddressSync([Buffer.from("token-vault"), existingMint.toBuffer()], program.programId);
+    const existingFeeVault = feeVaultFor(existingMint, existingMint);
+    const existingRewardToken = rewardTokenFor(existingMint, existingMint);
+    const [existingSlopeSchedule] = PublicKey.findProgramAddressSync([Buffer.from("slope-schedule"), existingMint.toBuffer()], program.programId);
+
+    const initializeExisting = (signer: PublicKey) =>
+      program.methods
+        .initializeWithMint(new anchor.BN(4), new anchor.BN(1), new anchor.BN(4), new anchor.BN(MIN_LOCK_DURATION), new anchor.BN(MAX_LOCK_DURATION))
+        .accountsStrict({
+          authority: signer,
+          globalState: existingGlobalState,
+          baseMint: existingMint,
+          veMint: existingVeMintKeypair.publicKey,
+          tokenVault: existingTokenVault,
+          feeVault: existingFeeVault,
+          slopeSchedule: existingSlopeSchedule,
+          rewardToken: existingRewardToken,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          veTokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        });
+
+    try {
+      await initializeExisting(user1.publicKey).signers([user1, existingVeMintKeypair]).rpc();
+      assert.fail("Expected NotMintAuthority");
+    } catch (err) {
+      assert.include(err.toString(), "NotMintAuthority");
+    }
+
+    await initializeExisting(authority.publicKey).signers([existingVeMintKeypair]).rpc();
+
+    const baseMintInfo = await getMint(provider.connection, existingMint, undefined, TOKEN_2022_PROGRAM_ID);
+    const veMintInfo = await getMint(provider.connection, existingVeMintKeypair.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+    assert.equal(baseMintInfo.mintAuthority?.toString(), authority.publicKey.toString());
+    assert.equal(veMintInfo.decimals, 6);
+
+    console.log("✓ Existing 6-decimal mint wrapped without taking its authority");
+  });
+
+  it("Initializes an instance for a legacy SPL Token mint with a Token-2022 ve mint", async () => {
+    const legacyMint = await createMint(
+      provider.connection,
+      authority.payer,
+      authority.publicKey,
+      null,
+      9,
+      Keypair.generate(),
+      undefined,
+      TOKEN_PROGRAM_ID
+    );
+    const legacyVeMintKeypair = Keypair.generate();
+
+    const [legacyGlobalState] = PublicKey.findProgramAddressSync([Buffer.from("global-state"), legacyMint.toBuffer()], program.programId);
+    const [legacyTokenVault] = PublicKey.findProgramAddressSync([Buffer.from("token-vault"), legacyMint.toBuffer()], program.programId);
+    const legacyFeeVault = feeVaultFor(legacyMint, legacyMint);
+    const legacyRewardToken = rewardTokenFor(legacyMint, legacyMint);
+    const [legacySlopeSchedule] = PublicKey.findProgramAddressSync([Buffer.from("slope-schedule"), legacyMint.toBuffer()], program.programId);
+
+    await program.methods
+      .initializeWithMint(new anchor.BN(4), new anchor.BN(1), new anchor.BN(4), new anchor.BN(MIN_LOCK_DURATION), new anchor.BN(MAX_LOCK_DURATION))
+      .accountsStrict({
+        authority: authority.publicKey,
+        globalState: legacyGlobalState,
+        baseMint: legacyMint,
+        veMint: legacyVeMintKeypair.publicKey,
+        tokenVault: legacyTokenVault,
+        feeVault: legacyFeeVault,
+        slopeSchedule: legacySlopeSchedule,
+        rewardToken: legacyRewardToken,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        veTokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([legacyVeMintKeypair])
+      .rpc();
+
+    const tokenVaultInfo = await provider.connection.getAccountInfo(legacyTokenVault);
+    const veMintInfo = await provider.connection.getAccountInfo(legacyVeMintKeypair.publicKey);
+    assert.equal(tokenVaultInfo.owner.toString(), TOKEN_PROGRAM_ID.toString());
+    assert.equal(veMintInfo.owner.toString(), TOKEN_2022_PROGRAM_ID.toString());
+
+    console.log("✓ Legacy SPL base mint paired with a Token-2022 ve mint");
+  });
+
+  it("Configures the devnet faucet", async () => {
+    [faucetState] = PublicKey.findProgramAddressSync([Buffer.from("faucet-state"), baseMint.toBuffer()], program.programId);
+
+    await program.methods
+      .configureFaucet(new anchor.BN(10000 * 10 ** 9), new anchor.BN(60 * 60), new anchor.BN(20000 * 10 ** 9), [user1.publicKey, user2.publicKey])
+      .accountsStrict({
+        authority: authority.publicKey,
+        globalState,
+        faucetState,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const faucetStateAccount = await program.account.faucetState.fetch(faucetState);
+    assert.equal(faucetStateAccount.totalCap.toString(), (20000 * 10 ** 9).toString());
+
+    console.log("✓ Faucet configured with 1h cooldown, 20000 token cap and two allowlisted users");
+  });
+
   it("Mints base tokens to test users", async () => {
     const user1TokenAccount = getAssociatedTokenAddressSync(baseMint, user1.publicKey, false, TOKEN_2022_PROGRAM_ID);
     const user2TokenAccount = getAssociatedTokenAddressSync(baseMint, user2.publicKey, false, TOKEN_2022_PROGRAM_ID);
@@ -79,29 +357,37 @@ describe("Fractional Ownership veToken System", () => {
     await program.methods
       .mintTokens(new anchor.BN(1000 * 10 ** 9))
       .accountsStrict({
-        authority: authority.publicKey,
+        authority: user1.publicKey,
         globalState,
+        faucetState,
+        faucetClaim: faucetClaimFor(user1.publicKey),
         baseMint,
         recipientTokenAccount: user1TokenAccount,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .preInstructions([
         createAssociatedTokenAccountInstruction(authority.publicKey, user1TokenAccount, user1.publicKey, baseMint, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID)
       ])
+      .signers([user1])
       .rpc();
 
     await program.methods
       .mintTokens(new anchor.BN(500 * 10 ** 9))
       .accountsStrict({
-        authority: authority.publicKey,
+        authority: user2.publicKey,
         globalState,
+        faucetState,
+        faucetClaim: faucetClaimFor(user2.publicKey),
         baseMint,
         recipientTokenAccount: user2TokenAccount,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .preInstructions([
         createAssociatedTokenAccountInstruction(authority.publicKey, user2TokenAccount, user2.publicKey, baseMint, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID)
       ])
+      .signers([user2])
       .rpc();
 
     await program.methods
@@ -109,9 +395,12 @@ describe("Fractional Ownership veToken System", () => {
       .accountsStrict({
         authority: authority.publicKey,
         globalState,
+        faucetState,
+        faucetClaim: faucetClaimFor(authority.publicKey),
         baseMint,
         recipientTokenAccount: authorityTokenAccount,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .preInstructions([
         createAssociatedTokenAccountInstruction(authority.publicKey, authorityTokenAccount, authority.publicKey, baseMint, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID)
@@ -121,23 +410,65 @@ describe("Fractional Ownership veToken System", () => {
     console.log("✓ Minted 1000 tokens to user1, 500 to user2, 10000 to authority");
   });
 
+  it("Rate-limits repeat faucet mints by the same signer", async () => {
+    const mintAsUser1 = (recipientTokenAccount: PublicKey) =>
+      program.methods
+        .mintTokens(new anchor.BN(1 * 10 ** 9))
+        .accountsStrict({
+          authority: user1.publicKey,
+          globalState,
+          faucetState,
+          faucetClaim: faucetClaimFor(user1.publicKey),
+          baseMint,
+          recipientTokenAccount,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+    try {
+      await mintAsUser1(getAssociatedTokenAddressSync(baseMint, user1.publicKey, false, TOKEN_2022_PROGRAM_ID));
+      assert.fail("Expected FaucetCooldown");
+    } catch (err) {
+      assert.include(err.toString(), "FaucetCooldown");
+    }
+
+    // A different recipient doesn't reset the signer's cooldown
+    try {
+      await mintAsUser1(getAssociatedTokenAddressSync(baseMint, user2.publicKey, false, TOKEN_2022_PROGRAM_ID));
+      assert.fail("Expected FaucetCooldown");
+    } catch (err) {
+      assert.include(err.toString(), "FaucetCooldown");
+    }
+
+    console.log("✓ Second faucet mint inside the cooldown rejected for any recipient");
+  });
+
   it("User1 locks 500 tokens for maximum duration (4 years)", async () => {
-    const [userLock] = PublicKey.findProgramAddressSync([Buffer.from("user-lock"), user1.publicKey.toBuffer()], program.programId);
+    const userLock = userLockFor(user1.publicKey);
     const userTokenAccount = getAssociatedTokenAddressSync(baseMint, user1.publicKey, false, TOKEN_2022_PROGRAM_ID);
-    const userVeTokenAccount = getAssociatedTokenAddressSync(veMint, user1.publicKey, false, TOKEN_2022_PROGRAM_ID);
 
     await program.methods
-      .lockTokens(new anchor.BN(500 * 10 ** 9), new anchor.BN(MAX_LOCK_DURATION))
+      .lockTokens(new anchor.BN(0), new anchor.BN(500 * 10 ** 9), new anchor.BN(MAX_LOCK_DURATION))
       .accountsStrict({
         user: user1.publicKey,
         userLock,
+        positionMint: positionMintFor(userLock),
+        positionTokenAccount: positionTokenAccountFor(user1.publicKey, userLock),
+        lockPoint: await nextLockPoint(userLock),
         globalState,
         baseMint,
         veMint,
         userTokenAccount,
-        userVeTokenAccount,
+        veEscrow: veEscrowFor(userLock),
         tokenVault,
+        slopeSchedule,
+        rewardToken: rewardTokenFor(baseMint),
+        feeEpoch: feeEpochFor(await currentEpochStart()),
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        veTokenProgram: TOKEN_2022_PROGRAM_ID,
+        positionTokenProgram: TOKEN_2022_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
@@ -147,27 +478,37 @@ describe("Fractional Ownership veToken System", () => {
     const userLockAccount = await program.account.userLock.fetch(userLock);
     assert.equal(userLockAccount.lockedAmount.toNumber(), 500 * 10 ** 9);
     assert.equal(userLockAccount.initialVeAmount.toNumber(), 2000 * 10 ** 9);
+    assert.equal(userLockAccount.lockMultiplierNumerator.toNumber(), 4);
+    const escrowBalance = await provider.connection.getTokenAccountBalance(veEscrowFor(userLock));
+    assert.equal(escrowBalance.value.amount, (2000 * 10 ** 9).toString(), "veTokens are held in the position's escrow");
 
     console.log("✓ User1 locked 500 tokens → received 2000 veTokens (4x multiplier)");
   });
 
   it("User2 locks 200 tokens for minimum duration (7 days)", async () => {
-    const [userLock] = PublicKey.findProgramAddressSync([Buffer.from("user-lock"), user2.publicKey.toBuffer()], program.programId);
+    const userLock = userLockFor(user2.publicKey);
     const userTokenAccount = getAssociatedTokenAddressSync(baseMint, user2.publicKey, false, TOKEN_2022_PROGRAM_ID);
-    const userVeTokenAccount = getAssociatedTokenAddressSync(veMint, user2.publicKey, false, TOKEN_2022_PROGRAM_ID);
 
     await program.methods
-      .lockTokens(new anchor.BN(200 * 10 ** 9), new anchor.BN(MIN_LOCK_DURATION))
+      .lockTokens(new anchor.BN(0), new anchor.BN(200 * 10 ** 9), new anchor.BN(MIN_LOCK_DURATION))
       .accountsStrict({
         user: user2.publicKey,
         userLock,
+        positionMint: positionMintFor(userLock),
+        positionTokenAccount: positionTokenAccountFor(user2.publicKey, userLock),
+        lockPoint: await nextLockPoint(userLock),
         globalState,
         baseMint,
         veMint,
         userTokenAccount,
-        userVeTokenAccount,
+        veEscrow: veEscrowFor(userLock),
         tokenVault,
+        slopeSchedule,
+        rewardToken: rewardTokenFor(baseMint),
+        feeEpoch: feeEpochFor(await currentEpochStart()),
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        veTokenProgram: TOKEN_2022_PROGRAM_ID,
+        positionTokenProgram: TOKEN_2022_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
@@ -182,66 +523,698 @@ describe("Fractional Ownership veToken System", () => {
   });
 
   it("Admin deposits 1000 tokens as protocol fees", async () => {
-    const authorityTokenAccount = getAssociatedTokenAddressSync(baseMint, authority.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const depositorTokenAccount = getAssociatedTokenAddressSync(baseMint, authority.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const epochStart = await currentEpochStart();
+    const feeEpoch = feeEpochFor(epochStart);
 
     await program.methods
       .depositFees(new anchor.BN(1000 * 10 ** 9))
       .accountsStrict({
-        authority: authority.publicKey,
+        depositor: authority.publicKey,
+        payer: authority.publicKey,
         globalState,
-        baseMint,
-        authorityTokenAccount,
+        rewardToken,
+        rewardMint: baseMint,
+        depositorTokenAccount,
+        treasuryTokenAccount: null,
         feeVault,
+        slopeSchedule,
+        feeEpoch,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .rpc();
 
     const globalStateAccount = await program.account.globalState.fetch(globalState);
-    assert.equal(globalStateAccount.totalFeesDeposited.toNumber(), 1000 * 10 ** 9);
+    const rewardTokenAccount = await program.account.rewardToken.fetch(rewardToken);
+    assert.equal(rewardTokenAccount.totalDeposited.toNumber(), 1000 * 10 ** 9);
 
-    console.log("✓ Admin deposited 1000 tokens to fee vault");
+    const feeEpochAccount = await program.account.feeEpoch.fetch(feeEpoch);
+    assert.equal(feeEpochAccount.epochStart.toNumber(), epochStart);
+    // An epoch that opened with no ve supply holds its fees back for the next one
+    assert.equal(feeEpochAccount.totalFees.toNumber() + rewardTokenAccount.undistributedFees.toNumber(), 1000 * 10 ** 9);
+    assert.equal(feeEpochAccount.veSupply.toString(), globalStateAccount.veEpochSupply.toString(), "Epoch priced off the supply at its start");
+
+    console.log("✓ Admin deposited 1000 tokens into the current fee epoch");
   });
 
-  it("User1 claims proportional fees based on veToken balance", async () => {
-    const [userLock] = PublicKey.findProgramAddressSync([Buffer.from("user-lock"), user1.publicKey.toBuffer()], program.programId);
+  it("User1 cannot claim fees from the still-open epoch", async () => {
+    const userLock = userLockFor(user1.publicKey);
     const userTokenAccount = getAssociatedTokenAddressSync(baseMint, user1.publicKey, false, TOKEN_2022_PROGRAM_ID);
 
+    try {
+      await program.methods
+        .claimFees()
+        .accountsStrict({
+          user: user1.publicKey,
+          userLock,
+          positionTokenAccount: positionTokenAccountFor(user1.publicKey, userLock),
+          userReward: userRewardFor(userLock, baseMint),
+          globalState,
+          slopeSchedule,
+          rewardToken,
+          rewardMint: baseMint,
+          userTokenAccount,
+          feeVault,
+          feeEpoch: feeEpochFor(await currentEpochStart()),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([{ pubkey: lockPointAt(userLock, 0), isWritable: false, isSigner: false }])
+        .signers([user1])
+        .rpc();
+      assert.fail("Expected NoFeesToClaim");
+    } catch (err) {
+      assert.include(err.toString(), "NoFeesToClaim");
+    }
+
+    const userLockAccount = await program.account.userLock.fetch(userLock);
+    assert.equal(userLockAccount.pointCount.toNumber(), 1, "First lock archived the empty starting line");
+
+    console.log("✓ User1's share of this week's fees unlocks once the epoch closes");
+  });
+
+  it("User1 cannot compound fees from the still-open epoch", async () => {
+    const userLock = userLockFor(user1.publicKey);
+    const lockBefore = await program.account.userLock.fetch(userLock);
+
+    try {
+      await program.methods
+        .compoundFees()
+        .accountsStrict({
+          user: user1.publicKey,
+          userLock,
+          positionTokenAccount: positionTokenAccountFor(user1.publicKey, userLock),
+          lockPoint: await nextLockPoint(userLock),
+          userReward: userRewardFor(userLock, baseMint),
+          globalState,
+          slopeSchedule,
+          rewardToken,
+          baseMint,
+          veMint,
+          veEscrow: veEscrowFor(userLock),
+          feeVault,
+          tokenVault,
+          feeEpoch: feeEpochFor(await currentEpochStart()),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          veTokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([{ pubkey: lockPointAt(userLock, 0), isWritable: false, isSigner: false }])
+        .signers([user1])
+        .rpc();
+      assert.fail("Expected NoFeesToClaim");
+    } catch (err) {
+      assert.include(err.toString(), "NoFeesToClaim");
+    }
+
+    const lockAfter = await program.account.userLock.fetch(userLock);
+    assert.equal(lockAfter.lockedAmount.toString(), lockBefore.lockedAmount.toString());
+    assert.equal(lockAfter.pointCount.toNumber(), lockBefore.pointCount.toNumber(), "Failed compound archives nothing");
+
+    console.log("✓ Compounding waits for the epoch to close, like claiming");
+  });
+
+  it("Owner delegates fee claims to a keeper paying a cold wallet", async () => {
+    const userLock = userLockFor(user1.publicKey);
+    const keeper = Keypair.generate();
+    const coldWallet = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(keeper.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+    );
+    const coldTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority.payer,
+      baseMint,
+      coldWallet.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
     await program.methods
-      .claimFees()
+      .setClaimDelegate(keeper.publicKey, coldWallet.publicKey)
       .accountsStrict({
         user: user1.publicKey,
         userLock,
+        positionTokenAccount: positionTokenAccountFor(user1.publicKey, userLock),
         globalState,
-        baseMint,
-        userTokenAccount,
-        feeVault,
-        tokenProgram: TOKEN_2022_PROGRAM_ID,
       })
       .signers([user1])
       .rpc();
 
-    console.log("✓ User1 claimed fees proportional to veToken balance");
+    const userLockAccount = await program.account.userLock.fetch(userLock);
+    assert.equal(userLockAccount.delegate.toString(), keeper.publicKey.toString());
+    assert.equal(userLockAccount.feeRecipient.toString(), coldWallet.publicKey.toString());
+
+    const claimFor = (caller: Keypair, recipientTokenAccount: PublicKey) =>
+      currentEpochStart().then((epochStart) =>
+        program.methods
+          .claimFeesFor()
+          .accountsStrict({
+            caller: caller.publicKey,
+            userLock,
+            positionTokenAccount: positionTokenAccountFor(user1.publicKey, userLock),
+            userReward: userRewardFor(userLock, baseMint),
+            globalState,
+            slopeSchedule,
+            rewardToken,
+            rewardMint: baseMint,
+            recipientTokenAccount,
+            feeVault,
+            feeEpoch: feeEpochFor(epochStart),
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([{ pubkey: lockPointAt(userLock, 0), isWritable: false, isSigner: false }])
+          .signers([caller])
+          .rpc()
+      );
+
+    try {
+      await claimFor(user2, coldTokenAccount.address);
+      assert.fail("Expected a constraint error for a caller that is neither owner nor delegate");
+    } catch (err) {
+      assert.include(err.toString(), "ConstraintRaw");
+    }
+
+    try {
+      await claimFor(keeper, getAssociatedTokenAddressSync(baseMint, user1.publicKey, false, TOKEN_2022_PROGRAM_ID));
+      assert.fail("Expected a constraint error for a recipient the owner did not choose");
+    } catch (err) {
+      assert.include(err.toString(), "ConstraintRaw");
+    }
+
+    // Passes the caller and recipient checks; the open epoch has nothing to pay yet
+    try {
+      await claimFor(keeper, coldTokenAccount.address);
+      assert.fail("Expected NoFeesToClaim");
+    } catch (err) {
+      assert.include(err.toString(), "NoFeesToClaim");
+    }
+
+    console.log("✓ Keeper can sweep user1's fees, only into the owner's cold wallet");
   });
 
-  it("User2 claims proportional fees based on veToken balance", async () => {
-    const [userLock] = PublicKey.findProgramAddressSync([Buffer.from("user-lock"), user2.publicKey.toBuffer()], program.programId);
+  it("User2 cannot claim fees from the still-open epoch", async () => {
+    const userLock = userLockFor(user2.publicKey);
     const userTokenAccount = getAssociatedTokenAddressSync(baseMint, user2.publicKey, false, TOKEN_2022_PROGRAM_ID);
 
+    try {
+      await program.methods
+        .claimFees()
+        .accountsStrict({
+          user: user2.publicKey,
+          userLock,
+          positionTokenAccount: positionTokenAccountFor(user2.publicKey, userLock),
+          userReward: userRewardFor(userLock, baseMint),
+          globalState,
+          slopeSchedule,
+          rewardToken,
+          rewardMint: baseMint,
+          userTokenAccount,
+          feeVault,
+          feeEpoch: feeEpochFor(await currentEpochStart()),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([{ pubkey: lockPointAt(userLock, 0), isWritable: false, isSigner: false }])
+        .signers([user2])
+        .rpc();
+      assert.fail("Expected NoFeesToClaim");
+    } catch (err) {
+      assert.include(err.toString(), "NoFeesToClaim");
+    }
+
+    const userLockAccount = await program.account.userLock.fetch(userLock);
+    assert.equal(userLockAccount.pointCount.toNumber(), 1, "First lock archived the empty starting line");
+
+    console.log("✓ User2's share of this week's fees unlocks once the epoch closes");
+  });
+
+  it("Batch claim validates every entry and skips locks with nothing pending", async () => {
+    const user1Lock = userLockFor(user1.publicKey);
+    const user2Lock = userLockFor(user2.publicKey);
+    const user1LockAccount = await program.account.userLock.fetch(user1Lock);
+    const user1Recipient = getAssociatedTokenAddressSync(baseMint, user1LockAccount.feeRecipient, false, TOKEN_2022_PROGRAM_ID);
+    const user2Recipient = getAssociatedTokenAddressSync(baseMint, user2.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const epochStart = await currentEpochStart();
+
+    const entry = (userLock: PublicKey, recipient: PublicKey) => [
+      { pubkey: userLock, isWritable: false, isSigner: false },
+      { pubkey: userRewardFor(userLock, baseMint), isWritable: true, isSigner: false },
+      { pubkey: positionTokenAccountFor(userLock.equals(user1Lock) ? user1.publicKey : user2.publicKey, userLock), isWritable: false, isSigner: false },
+      { pubkey: recipient, isWritable: true, isSigner: false },
+      { pubkey: lockPointAt(userLock, 0), isWritable: false, isSigner: false },
+    ];
+    const batch = (entries: { pubkey: PublicKey; isWritable: boolean; isSigner: boolean }[]) =>
+      program.methods
+        .claimFeesBatch(new anchor.BN(epochStart), 0, Buffer.from([1, 1]))
+        .accountsStrict({
+          caller: authority.publicKey,
+          globalState,
+          slopeSchedule,
+          rewardToken,
+          rewardMint: baseMint,
+          feeVault,
+          feeEpoch: feeEpochFor(epochStart),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(entries)
+        .rpc();
+
+    try {
+      await batch([...entry(user1Lock, user1Recipient), ...entry(user2Lock, user1Recipient)]);
+      assert.fail("Expected InvalidClaimEntry for a recipient the lock owner did not choose");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidClaimEntry");
+    }
+
+    // Neither lock has a closed epoch to claim yet: both are skipped, not rejected
+    await batch([...entry(user1Lock, user1Recipient), ...entry(user2Lock, user2Recipient)]);
+
+    const user2Reward = await provider.connection.getAccountInfo(userRewardFor(user2Lock, baseMint));
+    assert.isNull(user2Reward, "No claim cursor is created for a lock that earned nothing");
+
+    console.log("✓ Batch claim crank skips zero-pending locks");
+  });
+
+  it("Registers a second reward mint and deposits fees in it", async () => {
+    usdcMint = await createMint(
+      provider.connection,
+      authority.payer,
+      authority.publicKey,
+      null,
+      6,
+      Keypair.generate(),
+      undefined,
+      TOKEN_PROGRAM_ID
+    );
+    const usdcRewardToken = rewardTokenFor(usdcMint);
+    const usdcFeeVault = feeVaultFor(usdcMint);
+    const depositorTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority.payer,
+      usdcMint,
+      authority.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_PROGRAM_ID
+    );
+    await mintTo(provider.connection, authority.payer, usdcMint, depositorTokenAccount.address, authority.publicKey, 1100 * 10 ** 6, [], undefined, TOKEN_PROGRAM_ID);
+
     await program.methods
-      .claimFees()
+      .registerRewardMint()
       .accountsStrict({
-        user: user2.publicKey,
-        userLock,
+        authority: authority.publicKey,
         globalState,
-        baseMint,
-        userTokenAccount,
-        feeVault,
-        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        rewardMint: usdcMint,
+        rewardToken: usdcRewardToken,
+        feeVault: usdcFeeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
-      .signers([user2])
       .rpc();
 
-    console.log("✓ User2 claimed fees proportional to veToken balance");
+    const epochStart = await currentEpochStart();
+    const usdcFeeEpoch = feeEpochFor(epochStart, usdcMint);
+
+    await program.methods
+      .depositFees(new anchor.BN(50 * 10 ** 6))
+      .accountsStrict({
+        depositor: authority.publicKey,
+        payer: authority.publicKey,
+        globalState,
+        rewardToken: usdcRewardToken,
+        rewardMint: usdcMint,
+        depositorTokenAccount: depositorTokenAccount.address,
+        treasuryTokenAccount: null,
+        feeVault: usdcFeeVault,
+        slopeSchedule,
+        feeEpoch: usdcFeeEpoch,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const usdcEpochAccount = await program.account.feeEpoch.fetch(usdcFeeEpoch);
+    const usdcRewardTokenAccount = await program.account.rewardToken.fetch(usdcRewardToken);
+    const baseEpochAccount = await program.account.feeEpoch.fetch(feeEpochFor(epochStart));
+    const vaultBalance = await provider.connection.getTokenAccountBalance(usdcFeeVault);
+    assert.equal(usdcEpochAccount.rewardMint.toString(), usdcMint.toString());
+    assert.equal(usdcEpochAccount.totalFees.toNumber() + usdcRewardTokenAccount.undistributedFees.toNumber(), 50 * 10 ** 6);
+    assert.equal(usdcEpochAccount.veSupply.toString(), baseEpochAccount.veSupply.toString(), "All mints share the epoch's ve supply");
+    assert.equal(vaultBalance.value.amount, (50 * 10 ** 6).toString());
+
+    console.log("✓ USDC registered as a reward mint with its own vault and fee epochs");
+  });
+
+  it("Any signer deposits fees from a token account it owns", async () => {
+    // Stands in for a program PDA pushing revenue over CPI: not the fee depositor, not an ATA
+    const revenueSource = Keypair.generate();
+    const sourceTokenAccount = await createAccount(
+      provider.connection,
+      authority.payer,
+      usdcMint,
+      revenueSource.publicKey,
+      Keypair.generate(),
+      undefined,
+      TOKEN_PROGRAM_ID
+    );
+    await mintTo(provider.connection, authority.payer, usdcMint, sourceTokenAccount, authority.publicKey, 5 * 10 ** 6, [], undefined, TOKEN_PROGRAM_ID);
+
+    const usdcFeeVault = feeVaultFor(usdcMint);
+    const vaultBefore = Number((await provider.connection.getTokenAccountBalance(usdcFeeVault)).value.amount);
+
+    await program.methods
+      .depositFees(new anchor.BN(5 * 10 ** 6))
+      .accountsStrict({
+        depositor: revenueSource.publicKey,
+        payer: authority.publicKey,
+        globalState,
+        rewardToken: rewardTokenFor(usdcMint),
+        rewardMint: usdcMint,
+        depositorTokenAccount: sourceTokenAccount,
+        treasuryTokenAccount: null,
+        feeVault: usdcFeeVault,
+        slopeSchedule,
+        feeEpoch: feeEpochFor(await currentEpochStart(), usdcMint),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([revenueSource])
+      .rpc();
+
+    const vaultAfter = Number((await provider.connection.getTokenAccountBalance(usdcFeeVault)).value.amount);
+    assert.equal(vaultAfter - vaultBefore, 5 * 10 ** 6);
+
+    console.log("✓ Permissionless deposit from a non-ATA source account");
+  });
+
+  it("Streams rewards into the epoch of the week they are emitted in", async () => {
+    const usdcRewardToken = rewardTokenFor(usdcMint);
+    const depositorTokenAccount = getAssociatedTokenAddressSync(usdcMint, authority.publicKey, false, TOKEN_PROGRAM_ID);
+    const epochStart = await currentEpochStart();
+    const usdcFeeEpoch = feeEpochFor(epochStart, usdcMint);
+    const amount = 1000 * 10 ** 6;
+
+    const heldBefore = async () => {
+      const epoch = await program.account.feeEpoch.fetchNullable(usdcFeeEpoch);
+      const rewardToken = await program.account.rewardToken.fetch(usdcRewardToken);
+      return (epoch ? epoch.totalFees.toNumber() : 0) + rewardToken.undistributedFees.toNumber();
+    };
+    const heldBeforeNotify = await heldBefore();
+
+    await program.methods
+      .notifyReward(new anchor.BN(amount), new anchor.BN(WEEK))
+      .accountsStrict({
+        depositor: authority.publicKey,
+        globalState,
+        slopeSchedule,
+        rewardToken: usdcRewardToken,
+        rewardMint: usdcMint,
+        depositorTokenAccount,
+        feeVault: feeVaultFor(usdcMint),
+        feeEpoch: usdcFeeEpoch,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const stream = await program.account.rewardToken.fetch(usdcRewardToken);
+    const rate = stream.rewardRate.toNumber();
+    const notifiedAt = stream.periodFinish.toNumber() - WEEK;
+    assert.equal(rate, Math.floor(amount / WEEK));
+    assert.equal(stream.totalDeposited.toNumber(), 1050 * 10 ** 6);
+
+    // The open epoch takes the stream up to the end of its week straight away
+    const weekEnd = epochStart + WEEK;
+    assert.equal(stream.lastUpdateTime.toNumber(), weekEnd);
+    const heldAfterNotify = await heldBefore();
+    assert.equal(heldAfterNotify - heldBeforeNotify, (weekEnd - notifiedAt) * rate);
+
+    // A later touch in the same week has nothing more to credit
+    await program.methods
+      .depositFees(new anchor.BN(1))
+      .accountsStrict({
+        depositor: authority.publicKey,
+        payer: authority.publicKey,
+        globalState,
+        rewardToken: usdcRewardToken,
+        rewardMint: usdcMint,
+        depositorTokenAccount,
+        treasuryTokenAccount: null,
+        feeVault: feeVaultFor(usdcMint),
+        slopeSchedule,
+        feeEpoch: usdcFeeEpoch,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    assert.equal((await heldBefore()) - heldAfterNotify, 1, "Only the deposit itself");
+    const streamAfter = await program.account.rewardToken.fetch(usdcRewardToken);
+    assert.equal(streamAfter.lastUpdateTime.toNumber(), weekEnd);
+    assert.equal(streamAfter.periodFinish.toNumber(), stream.periodFinish.toNumber());
+
+    console.log("✓ Reward stream credited to the epoch of the week it is emitted in");
+  });
+
+  it("Admin recovers fees held back while there was no ve supply", async () => {
+    const usdcRewardToken = rewardTokenFor(usdcMint);
+    const recipientTokenAccount = getAssociatedTokenAddressSync(usdcMint, authority.publicKey, false, TOKEN_PROGRAM_ID);
+    const held = (await program.account.rewardToken.fetch(usdcRewardToken)).undistributedFees.toNumber();
+    assert.isAbove(held, 0, "This instance's first epoch opened with no ve supply");
+
+    const balanceBefore = Number((await provider.connection.getTokenAccountBalance(recipientTokenAccount)).value.amount);
+
+    await program.methods
+      .recoverUndistributedFees()
+      .accountsStrict({
+        authority: authority.publicKey,
+        globalState,
+        rewardToken: usdcRewardToken,
+        rewardMint: usdcMint,
+        feeVault: feeVaultFor(usdcMint),
+        recipientTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const balanceAfter = Number((await provider.connection.getTokenAccountBalance(recipientTokenAccount)).value.amount);
+    assert.equal(balanceAfter - balanceBefore, held);
+    assert.equal((await program.account.rewardToken.fetch(usdcRewardToken)).undistributedFees.toNumber(), 0);
+
+    console.log("✓ Undistributed USDC returned to the admin");
+  });
+
+  it("Routes the treasury and burn shares out of each deposit", async () => {
+    const treasuryWallet = Keypair.generate();
+    const usdcRewardToken = rewardTokenFor(usdcMint);
+    const usdcFeeVault = feeVaultFor(usdcMint);
+    const depositorTokenAccount = getAssociatedTokenAddressSync(usdcMint, authority.publicKey, false, TOKEN_PROGRAM_ID);
+    const treasuryTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority.payer,
+      usdcMint,
+      treasuryWallet.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_PROGRAM_ID
+    );
+    await mintTo(provider.connection, authority.payer, usdcMint, depositorTokenAccount, authority.publicKey, 100 * 10 ** 6, [], undefined, TOKEN_PROGRAM_ID);
+
+    try {
+      await program.methods
+        .setFeeSplit(treasuryWallet.publicKey, 8000, 2001)
+        .accountsStrict({ authority: authority.publicKey, globalState })
+        .rpc();
+      assert.fail("Expected InvalidFeeSplit");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidFeeSplit");
+    }
+
+    await program.methods
+      .setFeeSplit(treasuryWallet.publicKey, 1000, 500)
+      .accountsStrict({ authority: authority.publicKey, globalState })
+      .rpc();
+
+    const rewardTokenBefore = await program.account.rewardToken.fetch(usdcRewardToken);
+    const vaultBefore = Number((await provider.connection.getTokenAccountBalance(usdcFeeVault)).value.amount);
+    const supplyBefore = Number((await getMint(provider.connection, usdcMint, undefined, TOKEN_PROGRAM_ID)).supply);
+
+    const deposit = async (treasury: PublicKey | null) =>
+      program.methods
+        .depositFees(new anchor.BN(100 * 10 ** 6))
+        .accountsStrict({
+          depositor: authority.publicKey,
+          payer: authority.publicKey,
+          globalState,
+          rewardToken: usdcRewardToken,
+          rewardMint: usdcMint,
+          depositorTokenAccount,
+          feeVault: usdcFeeVault,
+          treasuryTokenAccount: treasury,
+          slopeSchedule,
+          feeEpoch: feeEpochFor(await currentEpochStart(), usdcMint),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    try {
+      await deposit(null);
+      assert.fail("Expected MissingTreasuryAccount");
+    } catch (err) {
+      assert.include(err.toString(), "MissingTreasuryAccount");
+    }
+
+    await deposit(treasuryTokenAccount.address);
+
+    const rewardTokenAfter = await program.account.rewardToken.fetch(usdcRewardToken);
+    const vaultAfter = Number((await provider.connection.getTokenAccountBalance(usdcFeeVault)).value.amount);
+    const supplyAfter = Number((await getMint(provider.connection, usdcMint, undefined, TOKEN_PROGRAM_ID)).supply);
+    const treasuryBalance = Number((await provider.connection.getTokenAccountBalance(treasuryTokenAccount.address)).value.amount);
+
+    assert.equal(treasuryBalance, 10 * 10 ** 6);
+    assert.equal(supplyBefore - supplyAfter, 5 * 10 ** 6);
+    assert.equal(vaultAfter - vaultBefore, 85 * 10 ** 6);
+    assert.equal(rewardTokenAfter.totalTreasuryFees.toNumber() - rewardTokenBefore.totalTreasuryFees.toNumber(), 10 * 10 ** 6);
+    assert.equal(rewardTokenAfter.totalBurned.toNumber() - rewardTokenBefore.totalBurned.toNumber(), 5 * 10 ** 6);
+
+    await program.methods
+      .setFeeSplit(authority.publicKey, 0, 0)
+      .accountsStrict({ authority: authority.publicKey, globalState })
+      .rpc();
+
+    console.log("✓ 10% to treasury, 5% burned, 85% to ve holders");
+  });
+
+  it("Transfers authority in two steps and assigns separate roles", async () => {
+    const newAdmin = Keypair.generate();
+    const feeBot = Keypair.generate();
+
+    await program.methods
+      .proposeAuthority(newAdmin.publicKey)
+      .accountsStrict({ authority: authority.publicKey, globalState })
+      .rpc();
+
+    let globalStateAccount = await program.account.globalState.fetch(globalState);
+    assert.equal(globalStateAccount.authority.toString(), authority.publicKey.toString());
+    assert.equal(globalStateAccount.pendingAuthority.toString(), newAdmin.publicKey.toString());
+
+    await program.methods
+      .acceptAuthority()
+      .accountsStrict({ newAuthority: newAdmin.publicKey, globalState })
+      .signers([newAdmin])
+      .rpc();
+
+    globalStateAccount = await program.account.globalState.fetch(globalState);
+    assert.equal(globalStateAccount.authority.toString(), newAdmin.publicKey.toString());
+    assert.equal(globalStateAccount.pendingAuthority.toString(), PublicKey.default.toString());
+
+    try {
+      await program.methods
+        .setRoles(feeBot.publicKey, authority.publicKey)
+        .accountsStrict({ authority: authority.publicKey, globalState })
+        .rpc();
+      assert.fail("Old authority should no longer set roles");
+    } catch (err) {
+      assert.include(err.toString(), "ConstraintRaw");
+    }
+
+    await program.methods
+      .proposeAuthority(authority.publicKey)
+      .accountsStrict({ authority: newAdmin.publicKey, globalState })
+      .signers([newAdmin])
+      .rpc();
+
+    await program.methods
+      .acceptAuthority()
+      .accountsStrict({ newAuthority: authority.publicKey, globalState })
+      .rpc();
+
+    await program.methods
+      .setRoles(feeBot.publicKey, authority.publicKey)
+      .accountsStrict({ authority: authority.publicKey, globalState })
+      .rpc();
+
+    globalStateAccount = await program.account.globalState.fetch(globalState);
+    assert.equal(globalStateAccount.authority.toString(), authority.publicKey.toString());
+    assert.equal(globalStateAccount.feeDepositor.toString(), feeBot.publicKey.toString());
+
+    await program.methods
+      .setRoles(authority.publicKey, authority.publicKey)
+      .accountsStrict({ authority: authority.publicKey, globalState })
+      .rpc();
+
+    console.log("✓ Authority handed over and back, fee depositor rotated independently");
+  });
+
+  it("Queues a lock multiplier update behind the timelock", async () => {
+    await program.methods
+      .queueMultiplierUpdate(new anchor.BN(3), new anchor.BN(1))
+      .accountsStrict({ authority: authority.publicKey, globalState })
+      .rpc();
+
+    const globalStateAccount = await program.account.globalState.fetch(globalState);
+    assert.equal(globalStateAccount.lockMultiplierNumerator.toNumber(), 4);
+    assert.equal(globalStateAccount.pendingLockMultiplierNumerator.toNumber(), 3);
+    assert.isAbove(globalStateAccount.lockMultiplierEffectiveAt.toNumber(), 0);
+
+    try {
+      await program.methods.applyMultiplierUpdate().accountsStrict({ globalState }).rpc();
+      assert.fail("Expected MultiplierUpdateNotReady");
+    } catch (err) {
+      assert.include(err.toString(), "MultiplierUpdateNotReady");
+    }
+
+    console.log("✓ Multiplier update queued; apply rejected before the delay elapses");
+  });
+
+  it("Pauser blocks fee deposits while paused", async () => {
+    const depositorTokenAccount = getAssociatedTokenAddressSync(baseMint, authority.publicKey, false, TOKEN_2022_PROGRAM_ID);
+
+    await program.methods
+      .setPauseState(true, false)
+      .accountsStrict({ pauser: authority.publicKey, globalState })
+      .rpc();
+
+    const epochStart = await currentEpochStart();
+
+    try {
+      await program.methods
+        .depositFees(new anchor.BN(1 * 10 ** 9))
+        .accountsStrict({
+          depositor: authority.publicKey,
+          payer: authority.publicKey,
+          globalState,
+          rewardToken,
+          rewardMint: baseMint,
+          depositorTokenAccount,
+          treasuryTokenAccount: null,
+          feeVault,
+          slopeSchedule,
+          feeEpoch: feeEpochFor(epochStart),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Expected ProtocolPaused");
+    } catch (err) {
+      assert.include(err.toString(), "ProtocolPaused");
+    }
+
+    await program.methods
+      .setPauseState(false, false)
+      .accountsStrict({ pauser: authority.publicKey, globalState })
+      .rpc();
+
+    console.log("✓ Deposits rejected while paused, resumed after unpause");
   });
 
   it("Verifies protocol state", async () => {
@@ -251,11 +1224,569 @@ describe("Fractional Ownership veToken System", () => {
     console.log("PROTOCOL STATE ");
     console.log(`Total Locked:     ${(globalStateAccount.totalLocked.toNumber() / 10 ** 9).toFixed(2).padStart(12)} tokens`);
     console.log(`Total veSupply:   ${(globalStateAccount.totalVeSupply.toNumber() / 10 ** 9).toFixed(2).padStart(12)} tokens`);
-    console.log(`Fees Deposited:   ${(globalStateAccount.totalFeesDeposited.toNumber() / 10 ** 9).toFixed(2).padStart(12)} tokens`);
+    console.log(`Fees Deposited:   ${((await program.account.rewardToken.fetch(rewardToken)).totalDeposited.toNumber() / 10 ** 9).toFixed(2).padStart(12)} tokens`);
 
     assert.equal(globalStateAccount.totalLocked.toNumber(), 700 * 10 ** 9, "User1 (500) + User2 (200) locked");
     assert.equal(globalStateAccount.totalVeSupply.toNumber(), 2200 * 10 ** 9, "User1 (2000) + User2 (200) veTokens");
 
+    await program.methods.checkpoint().accountsStrict({ globalState, slopeSchedule }).rpc();
+
+    const checkpointed = await program.account.globalState.fetch(globalState);
+    const decayedVeSupply = checkpointed.veBias.div(new anchor.BN(10 ** 12));
+    assert.isTrue(decayedVeSupply.gt(new anchor.BN(0)), "Active locks keep voting power");
+    assert.isTrue(decayedVeSupply.lte(checkpointed.totalVeSupply), "Decayed supply never exceeds the undecayed sum");
+
     console.log("All tests passed");
   });
+
+  it("Emergency shutdown lets users withdraw before unlock time", async () => {
+    const userLock = userLockFor(user2.publicKey);
+    const userTokenAccount = getAssociatedTokenAddressSync(baseMint, user2.publicKey, false, TOKEN_2022_PROGRAM_ID);
+
+    const withdrawAccounts = {
+      user: user2.publicKey,
+      userLock,
+      positionTokenAccount: positionTokenAccountFor(user2.publicKey, userLock),
+      lockPoint: await nextLockPoint(userLock),
+      userReward: userRewardFor(userLock, baseMint),
+      globalState,
+      slopeSchedule,
+      rewardToken: rewardTokenFor(baseMint),
+      baseMint,
+      veMint,
+      userTokenAccount,
+      veEscrow: veEscrowFor(userLock),
+      tokenVault,
+      feeVault: feeVaultFor(baseMint),
+      feeEpoch: feeEpochFor(await currentEpochStart()),
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+      veTokenProgram: TOKEN_2022_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+
+    try {
+      await program.methods.withdraw(false).accountsStrict(withdrawAccounts).signers([user2]).rpc();
+      assert.fail("Expected LockNotExpired");
+    } catch (err) {
+      assert.include(err.toString(), "LockNotExpired");
+    }
+
+    await program.methods
+      .setPauseState(true, true)
+      .accountsStrict({ pauser: authority.publicKey, globalState })
+      .rpc();
+
+    // Nothing has closed yet, so the claim settles the cursor and pays nothing
+    await program.methods.withdraw(true).accountsStrict(withdrawAccounts).signers([user2]).rpc();
+
+    const userLockAccount = await program.account.userLock.fetch(userLock);
+    const globalStateAccount = await program.account.globalState.fetch(globalState);
+    assert.equal(userLockAccount.lockedAmount.toNumber(), 0);
+    assert.equal(userLockAccount.pointCount.toNumber(), 2, "Withdrawal archived the pre-exit line for later claims");
+    const userReward = await program.account.userReward.fetch(userRewardFor(userLock, baseMint));
+    assert.isTrue(userReward.userLock.equals(userLock), "Claiming on withdraw opened the base-mint cursor");
+    assert.equal(globalStateAccount.totalLocked.toNumber(), 500 * 10 ** 9);
+
+    await program.methods
+      .setPauseState(false, false)
+      .accountsStrict({ pauser: authority.publicKey, globalState })
+      .rpc();
+
+    console.log("✓ User2 recovered principal early under emergency shutdown");
+  });
+
+  it("Top-ups archive the previous decay line so earlier epochs keep their pricing", async () => {
+    const userLock = userLockFor(user1.publicKey);
+    const userTokenAccount = getAssociatedTokenAddressSync(baseMint, user1.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const depositorTokenAccount = getAssociatedTokenAddressSync(baseMint, authority.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const epochStart = await currentEpochStart();
+    const feeEpoch = feeEpochFor(epochStart);
+
+    await program.methods
+      .depositFees(new anchor.BN(100 * 10 ** 9))
+      .accountsStrict({
+        depositor: authority.publicKey,
+        payer: authority.publicKey,
+        globalState,
+        rewardToken,
+        rewardMint: baseMint,
+        depositorTokenAccount,
+        treasuryTokenAccount: null,
+        feeVault,
+        slopeSchedule,
+        feeEpoch,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const lockBefore = await program.account.userLock.fetch(userLock);
+    const epochBefore = await program.account.feeEpoch.fetch(feeEpoch);
+    const baseRewardToken = await program.account.rewardToken.fetch(rewardToken);
+    assert.equal(
+      epochBefore.totalFees.toNumber() + baseRewardToken.undistributedFees.toNumber(),
+      1100 * 10 ** 9,
+      "Deposits in one week share an epoch"
+    );
+
+    await program.methods
+      .increaseLockAmount(new anchor.BN(400 * 10 ** 9))
+      .accountsStrict({
+        user: user1.publicKey,
+        userLock,
+        positionTokenAccount: positionTokenAccountFor(user1.publicKey, userLock),
+        lockPoint: await nextLockPoint(userLock),
+        globalState,
+        baseMint,
+        veMint,
+        userTokenAccount,
+        veEscrow: veEscrowFor(userLock),
+        tokenVault,
+        slopeSchedule,
+        rewardToken: rewardTokenFor(baseMint),
+        feeEpoch: feeEpochFor(await currentEpochStart()),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        veTokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    const archivedPoint = await program.account.lockPoint.fetch(lockPointAt(userLock, lockBefore.pointCount.toNumber()));
+    const lockAfter = await program.account.userLock.fetch(userLock);
+    const epochAfter = await program.account.feeEpoch.fetch(feeEpoch);
+    assert.equal(lockAfter.pointCount.toNumber(), lockBefore.pointCount.toNumber() + 1);
+    assert.equal(archivedPoint.initialVeAmount.toString(), lockBefore.initialVeAmount.toString(), "Archived line is the pre-top-up line");
+    assert.equal(archivedPoint.setTime.toString(), lockBefore.lastPointTime.toString());
+    assert.equal(epochAfter.veSupply.toString(), epochBefore.veSupply.toString(), "Top-up does not reprice the open epoch");
+
+    console.log("✓ Top-up archived the old line; the open epoch keeps its starting supply");
+  });
+
+  it("User1 opens a second position and voting power sums both", async () => {
+    const position0 = userLockFor(user1.publicKey, 0);
+    const position1 = userLockFor(user1.publicKey, 1);
+    const userTokenAccount = getAssociatedTokenAddressSync(baseMint, user1.publicKey, false, TOKEN_2022_PROGRAM_ID);
+
+    const lock = (positionId: number, userLock: PublicKey) =>
+      Promise.all([nextLockPoint(userLock), currentEpochStart()]).then(([lockPoint, epochStart]) =>
+        program.methods
+          .lockTokens(new anchor.BN(positionId), new anchor.BN(50 * 10 ** 9), new anchor.BN(MIN_LOCK_DURATION))
+          .accountsStrict({
+            user: user1.publicKey,
+            userLock,
+            positionMint: positionMintFor(userLock),
+            positionTokenAccount: positionTokenAccountFor(user1.publicKey, userLock),
+            lockPoint,
+            globalState,
+            baseMint,
+            veMint,
+            userTokenAccount,
+            veEscrow: veEscrowFor(userLock),
+            tokenVault,
+            slopeSchedule,
+            rewardToken: rewardTokenFor(baseMint),
+            feeEpoch: feeEpochFor(epochStart),
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            veTokenProgram: TOKEN_2022_PROGRAM_ID,
+            positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user1])
+          .rpc()
+      );
+
+    try {
+      await lock(0, position0);
+      assert.fail("Expected PositionInUse");
+    } catch (err) {
+      assert.include(err.toString(), "PositionInUse");
+    }
+
+    const position0Before = await program.account.userLock.fetch(position0);
+    await lock(1, position1);
+
+    const position0After = await program.account.userLock.fetch(position0);
+    const position1Account = await program.account.userLock.fetch(position1);
+    assert.equal(position1Account.positionId.toNumber(), 1);
+    assert.equal(position1Account.lockedAmount.toNumber(), 50 * 10 ** 9);
+    assert.equal(position0After.unlockTime.toString(), position0Before.unlockTime.toString(), "Positions keep their own unlock times");
+    assert.isBelow(position1Account.unlockTime.toNumber(), position0After.unlockTime.toNumber());
+
+    const votingPower = (positions: PublicKey[]) =>
+      program.methods
+        .getVotingPower(user1.publicKey)
+        .accountsStrict({ globalState })
+        .remainingAccounts(
+          [...positions]
+            .sort((a, b) => Buffer.compare(a.toBuffer(), b.toBuffer()))
+            .flatMap((userLock) => [
+              { pubkey: userLock, isWritable: false, isSigner: false },
+              { pubkey: positionTokenAccountFor(user1.publicKey, userLock), isWritable: false, isSigner: false },
+            ])
+        )
+        .view();
+
+    const firstOnly = await votingPower([position0]);
+    const total = await votingPower([position0, position1]);
+    assert.isTrue(total.gt(firstOnly), "Second position adds voting power");
+
+    try {
+      await votingPower([position0, position0]);
+      assert.fail("Expected InvalidPosition");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidPosition");
+    }
+
+    console.log("✓ Independent positions; voting power is their sum");
+  });
+
+  it("Position NFT moves control of the lock to its new holder", async () => {
+    const position1 = userLockFor(user1.publicKey, 1);
+    const positionMint = positionMintFor(position1);
+
+    const mintInfo = await getMint(provider.connection, positionMint, undefined, TOKEN_2022_PROGRAM_ID);
+    assert.equal(mintInfo.decimals, 0);
+    assert.equal(Number(mintInfo.supply), 1);
+
+    const user2PositionAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority.payer,
+      positionMint,
+      user2.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await transferChecked(
+      provider.connection,
+      authority.payer,
+      positionTokenAccountFor(user1.publicKey, position1),
+      positionMint,
+      user2PositionAccount.address,
+      user1,
+      1,
+      0,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    const increase = (holder: Keypair) =>
+      Promise.all([nextLockPoint(position1), currentEpochStart()]).then(([lockPoint, epochStart]) =>
+        program.methods
+          .increaseLockAmount(new anchor.BN(10 * 10 ** 9))
+          .accountsStrict({
+            user: holder.publicKey,
+            userLock: position1,
+            positionTokenAccount: positionTokenAccountFor(holder.publicKey, position1),
+            lockPoint,
+            globalState,
+            baseMint,
+            veMint,
+            userTokenAccount: getAssociatedTokenAddressSync(baseMint, holder.publicKey, false, TOKEN_2022_PROGRAM_ID),
+            veEscrow: veEscrowFor(position1),
+            tokenVault,
+            slopeSchedule,
+            rewardToken: rewardTokenFor(baseMint),
+            feeEpoch: feeEpochFor(epochStart),
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            veTokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([holder])
+          .rpc()
+      );
+
+    try {
+      await increase(user1);
+      assert.fail("Expected the previous holder to be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NotPositionHolder");
+    }
+
+    await increase(user2);
+    const positionAccount = await program.account.userLock.fetch(position1);
+    assert.equal(positionAccount.lockedAmount.toNumber(), 60 * 10 ** 9);
+    assert.equal(positionAccount.user.toString(), user1.publicKey.toString(), "The PDA stays keyed to the opening wallet");
+    const escrowBalance = await provider.connection.getTokenAccountBalance(veEscrowFor(position1));
+    assert.equal(escrowBalance.value.amount, positionAccount.initialVeAmount.toString(), "The position's ve moved with the NFT");
+
+    const holderPower = await program.methods
+      .getVotingPower(user2.publicKey)
+      .accountsStrict({ globalState })
+      .remainingAccounts([
+        { pubkey: position1, isWritable: false, isSigner: false },
+        { pubkey: user2PositionAccount.address, isWritable: false, isSigner: false },
+      ])
+      .view();
+    assert.isTrue(holderPower.gt(new anchor.BN(0)), "Voting power follows the NFT");
+
+    console.log("✓ Position NFT transferred; only the new holder controls the lock");
+  });
+
+  it("Splits part of a position into a new NFT-backed position", async () => {
+    const position1 = userLockFor(user1.publicKey, 1);
+    const newPosition = userLockFor(user2.publicKey, 5);
+
+    const split = async (amount: number) =>
+      program.methods
+        .splitLock(new anchor.BN(5), new anchor.BN(amount))
+        .accountsStrict({
+          user: user2.publicKey,
+          userLock: position1,
+          positionTokenAccount: positionTokenAccountFor(user2.publicKey, position1),
+          lockPoint: await nextLockPoint(position1),
+          newUserLock: newPosition,
+          newLockPoint: lockPointAt(newPosition, 0),
+          newPositionMint: positionMintFor(newPosition),
+          newPositionTokenAccount: positionTokenAccountFor(user2.publicKey, newPosition),
+          globalState,
+          slopeSchedule,
+          rewardToken: rewardTokenFor(baseMint),
+          feeEpoch: feeEpochFor(await currentEpochStart()),
+          veMint,
+          veEscrow: veEscrowFor(position1),
+          newVeEscrow: veEscrowFor(newPosition),
+          veTokenProgram: TOKEN_2022_PROGRAM_ID,
+          positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+    try {
+      await split(60 * 10 ** 9);
+      assert.fail("Expected splitting the whole position to fail");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidAmount");
+    }
+
+    const sourceBefore = await program.account.userLock.fetch(position1);
+    const stateBefore = await program.account.globalState.fetch(globalState);
+
+    await split(20 * 10 ** 9);
+
+    const source = await program.account.userLock.fetch(position1);
+    const created = await program.account.userLock.fetch(newPosition);
+    const stateAfter = await program.account.globalState.fetch(globalState);
+
+    assert.equal(source.lockedAmount.toNumber(), 40 * 10 ** 9);
+    assert.equal(created.lockedAmount.toNumber(), 20 * 10 ** 9);
+    assert.equal(created.unlockTime.toString(), sourceBefore.unlockTime.toString());
+    assert.equal(created.lockStartTime.toString(), sourceBefore.lockStartTime.toString());
+    assert.equal(
+      source.initialVeAmount.add(created.initialVeAmount).toString(),
+      sourceBefore.initialVeAmount.toString(),
+      "ve is divided, not minted"
+    );
+    assert.equal(stateAfter.totalLocked.toString(), stateBefore.totalLocked.toString());
+    assert.equal(stateAfter.totalVeSupply.toString(), stateBefore.totalVeSupply.toString());
+    const sourceEscrow = await provider.connection.getTokenAccountBalance(veEscrowFor(position1));
+    const newEscrow = await provider.connection.getTokenAccountBalance(veEscrowFor(newPosition));
+    assert.equal(sourceEscrow.value.amount, source.initialVeAmount.toString());
+    assert.equal(newEscrow.value.amount, created.initialVeAmount.toString(), "The split share of ve moved to the new escrow");
+
+    const nft = await getMint(provider.connection, positionMintFor(newPosition), undefined, TOKEN_2022_PROGRAM_ID);
+    assert.equal(Number(nft.supply), 1);
+
+    console.log("✓ Position split without changing protocol totals");
+  });
+
+  it("Merges one position into another and closes the source", async () => {
+    const destination = userLockFor(user1.publicKey, 1);
+    const source = userLockFor(user2.publicKey, 5);
+
+    const merge = async (sourceLock: PublicKey) =>
+      program.methods
+        .mergeLocks(0)
+        .accountsStrict({
+          user: user2.publicKey,
+          userLock: destination,
+          positionTokenAccount: positionTokenAccountFor(user2.publicKey, destination),
+          sourceLock,
+          sourcePositionMint: positionMintFor(sourceLock),
+          sourcePositionTokenAccount: positionTokenAccountFor(user2.publicKey, sourceLock),
+          lockPoint: await nextLockPoint(destination),
+          userReward: userRewardFor(destination, baseMint),
+          sourceUserReward: userRewardFor(sourceLock, baseMint),
+          globalState,
+          slopeSchedule,
+          rewardToken: rewardTokenFor(baseMint),
+          rewardMint: baseMint,
+          userTokenAccount: getAssociatedTokenAddressSync(baseMint, user2.publicKey, false, TOKEN_2022_PROGRAM_ID),
+          feeVault: feeVaultFor(baseMint),
+          feeEpoch: feeEpochFor(await currentEpochStart()),
+          veMint,
+          veEscrow: veEscrowFor(destination),
+          sourceVeEscrow: veEscrowFor(sourceLock),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          veTokenProgram: TOKEN_2022_PROGRAM_ID,
+          positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+    try {
+      await merge(destination);
+      assert.fail("Expected merging a position into itself to fail");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidPosition");
+    }
+
+    const destinationBefore = await program.account.userLock.fetch(destination);
+    const sourceBefore = await program.account.userLock.fetch(source);
+    const stateBefore = await program.account.globalState.fetch(globalState);
+
+    await merge(source);
+
+    const merged = await program.account.userLock.fetch(destination);
+    const stateAfter = await program.account.globalState.fetch(globalState);
+
+    assert.equal(merged.lockedAmount.toNumber(), 60 * 10 ** 9);
+    assert.equal(
+      merged.unlockTime.toNumber(),
+      Math.max(destinationBefore.unlockTime.toNumber(), sourceBefore.unlockTime.toNumber()),
+      "Merged position takes the later unlock time"
+    );
+    assert.equal(stateAfter.totalLocked.toString(), stateBefore.totalLocked.toString());
+    assert.equal(
+      stateAfter.totalVeSupply.sub(stateBefore.totalVeSupply).toString(),
+      merged.initialVeAmount.sub(destinationBefore.initialVeAmount).sub(sourceBefore.initialVeAmount).toString(),
+      "Only the ve delta is minted or burned"
+    );
+
+    const destinationEscrow = await provider.connection.getTokenAccountBalance(veEscrowFor(destination));
+    assert.equal(destinationEscrow.value.amount, merged.initialVeAmount.toString(), "Source ve joins the destination escrow");
+
+    assert.isNull(await program.account.userLock.fetchNullable(source), "Source position is closed");
+    assert.isNull(await program.account.userReward.fetchNullable(userRewardFor(source, baseMint)), "Source cursor is closed");
+    assert.isNull(await provider.connection.getAccountInfo(veEscrowFor(source)), "Source escrow is closed");
+    const sourceNft = await getMint(provider.connection, positionMintFor(source), undefined, TOKEN_2022_PROGRAM_ID);
+    assert.equal(Number(sourceNft.supply), 0, "Source position NFT is burned");
+
+    console.log("✓ Positions merged into one");
+  });
+
+  it("Early withdraw returns principal minus a capped penalty paid to lockers", async () => {
+    const userLock = userLockFor(user1.publicKey, 0);
+    const userTokenAccount = getAssociatedTokenAddressSync(baseMint, user1.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const rewardToken = rewardTokenFor(baseMint);
+    const feeEpoch = feeEpochFor(await currentEpochStart());
+
+    try {
+      await program.methods
+        .setEarlyWithdrawPenalty(10_001, 5_000)
+        .accountsStrict({ authority: authority.publicKey, globalState })
+        .rpc();
+      assert.fail("Expected InvalidPenaltyConfig");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidPenaltyConfig");
+    }
+
+    const state = await program.account.globalState.fetch(globalState);
+    assert.equal(state.earlyWithdrawPenaltyBps, 10_000);
+    assert.equal(state.earlyWithdrawPenaltyCapBps, 5_000);
+
+    const lockBefore = await program.account.userLock.fetch(userLock);
+    const lockedAmount = lockBefore.lockedAmount.toNumber();
+    const balanceBefore = (await provider.connection.getTokenAccountBalance(userTokenAccount)).value.amount;
+
+    await program.methods
+      .earlyWithdraw()
+      .accountsStrict({
+        user: user1.publicKey,
+        userLock,
+        positionTokenAccount: positionTokenAccountFor(user1.publicKey, userLock),
+        lockPoint: await nextLockPoint(userLock),
+        globalState,
+        slopeSchedule,
+        rewardToken,
+        baseMint,
+        veMint,
+        userTokenAccount,
+        veEscrow: veEscrowFor(userLock),
+        tokenVault,
+        feeVault: feeVaultFor(baseMint),
+        feeEpoch,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        veTokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    // More than half the maximum duration remains, so the cap applies
+    const penalty = lockedAmount / 2;
+    const balanceAfter = (await provider.connection.getTokenAccountBalance(userTokenAccount)).value.amount;
+    assert.equal(BigInt(balanceAfter) - BigInt(balanceBefore), BigInt(lockedAmount - penalty));
+
+    const lockAfter = await program.account.userLock.fetch(userLock);
+    assert.equal(lockAfter.lockedAmount.toNumber(), 0);
+
+    const rewardTokenAccount = await program.account.rewardToken.fetch(rewardToken);
+    assert.equal(rewardTokenAccount.totalPenalties.toNumber(), penalty);
+
+    // The open epoch still counts the leaver's ve, so the penalty waits for a later one
+    const epochAfter = await program.account.feeEpoch.fetch(feeEpoch);
+    assert.equal(rewardTokenAccount.deferredFees.toNumber(), penalty);
+    assert.equal(rewardTokenAccount.deferredEpoch.toNumber(), epochAfter.epochStart.toNumber());
+
+    console.log("✓ Early withdraw paid a capped penalty into fee distribution");
+  });
+
+  it("The new holder of a transferred position withdraws it", async () => {
+    const position1 = userLockFor(user1.publicKey, 1);
+    const userTokenAccount = getAssociatedTokenAddressSync(baseMint, user2.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const lockBefore = await program.account.userLock.fetch(position1);
+    const balanceBefore = (await provider.connection.getTokenAccountBalance(userTokenAccount)).value.amount;
+
+    // The position has not expired yet; shutdown releases it now
+    await program.methods
+      .setPauseState(true, true)
+      .accountsStrict({ pauser: authority.publicKey, globalState })
+      .rpc();
+
+    await program.methods
+      .withdraw(false)
+      .accountsStrict({
+        user: user2.publicKey,
+        userLock: position1,
+        positionTokenAccount: positionTokenAccountFor(user2.publicKey, position1),
+        lockPoint: await nextLockPoint(position1),
+        userReward: userRewardFor(position1, baseMint),
+        globalState,
+        slopeSchedule,
+        rewardToken: rewardTokenFor(baseMint),
+        baseMint,
+        veMint,
+        userTokenAccount,
+        veEscrow: veEscrowFor(position1),
+        tokenVault,
+        feeVault: feeVaultFor(baseMint),
+        feeEpoch: feeEpochFor(await currentEpochStart()),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        veTokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user2])
+      .rpc();
+
+    await program.methods
+      .setPauseState(false, false)
+      .accountsStrict({ pauser: authority.publicKey, globalState })
+      .rpc();
+
+    const balanceAfter = (await provider.connection.getTokenAccountBalance(userTokenAccount)).value.amount;
+    assert.equal((BigInt(balanceAfter) - BigInt(balanceBefore)).toString(), lockBefore.lockedAmount.toString());
+    const escrowBalance = await provider.connection.getTokenAccountBalance(veEscrowFor(position1));
+    assert.equal(escrowBalance.value.amount, "0", "The position's ve was burned from its escrow");
+    assert.equal((await program.account.userLock.fetch(position1)).lockedAmount.toNumber(), 0);
+
+    console.log("✓ Buyer of a position NFT withdrew it without holding the seller's veTokens");
+  });
 });